@@ -0,0 +1,50 @@
+use std::fmt::Write;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use wow_raid_analyzer::parser::LogFile;
+
+const ROWS: usize = 200_000;
+
+// A retail log with advanced logging, cycling through the most common events.
+fn synthetic_log() -> String {
+    let rows = [
+        "SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,81483954,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,488,488,-1,8,0,0,0,nil,nil,nil",
+        "SPELL_HEAL,Player-1379-0B0D2C10,\"Healer-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,2061,\"Flash Heal\",0x2,Player-1379-0A9FF58F,0000000000000000,450000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,50000,50000,0,0,nil",
+        "SWING_DAMAGE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,90000000,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,300000,300000,-1,1,0,0,0,nil,nil,nil",
+        "SPELL_AURA_APPLIED,Player-1379-0B0D2C10,\"Healer-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,17,\"Power Word: Shield\",0x2,BUFF,120000",
+        "SPELL_CAST_SUCCESS,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,213709,\"Brambles\",0x8,Player-1379-0A9FF58F,0000000000000000,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70",
+    ];
+
+    let mut log = String::from("4/29 20:15:03.000  COMBAT_LOG_VERSION,20,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,10.1.7,PROJECT_ID,1\n");
+    for i in 0..ROWS {
+        let ms = i * 7;
+        writeln!(
+            log,
+            "4/29 {:02}:{:02}:{:02}.{:03}  {}",
+            20 + ms / 3_600_000,
+            ms / 60_000 % 60,
+            ms / 1000 % 60,
+            ms % 1000,
+            rows[i % rows.len()]
+        )
+        .unwrap();
+    }
+
+    log
+}
+
+fn parse(c: &mut Criterion) {
+    let path = std::env::temp_dir().join("wow-log-analyzer-bench.txt");
+    std::fs::write(&path, synthetic_log()).unwrap();
+    let file = LogFile::open(&path).unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(std::fs::metadata(&path).unwrap().len()));
+    group.bench_function("single_threaded", |b| b.iter(|| file.rows().count()));
+    group.bench_function("multi_threaded", |b| b.iter(|| file.par_rows().count()));
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
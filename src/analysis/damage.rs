@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+
+use super::pets::PetOwners;
+use crate::parser::{Guid, LogCell, LogRow, SpellSchool};
+
+// Melee swings have no spell in the log, they are listed under the id of Auto Attack.
+const MELEE_SPELL_ID: i64 = 6603;
+const MELEE_SPELL_NAME: &str = "Melee";
+
+/// The damage done with one spell, as part of a `DamageDone`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpellDamageDone {
+    pub spell_id: i64,
+    pub name: String,
+    pub school: SpellSchool,
+    pub total: i64,
+    pub hits: u32,
+    pub crits: u32,
+    pub max_hit: i64,
+}
+
+impl SpellDamageDone {
+    pub fn crit_percent(&self) -> f64 {
+        self.crits as f64 * 100.0 / self.hits.max(1) as f64
+    }
+
+    pub fn average_hit(&self) -> i64 {
+        self.total / self.hits.max(1) as i64
+    }
+}
+
+/// The damage the pets of one name did, as part of a `DamageDone`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PetDamageDone {
+    pub name: String,
+    pub total: i64,
+    // Most damage first.
+    pub spells: Vec<SpellDamageDone>,
+}
+
+/// The damage a player did, or a pet when pets are not merged into their
+/// owners, from `DamageMeter::entries`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DamageDone {
+    pub guid: Guid,
+    pub name: String,
+    // The name of the player that owns the pet.
+    pub owner: Option<String>,
+    // Including the damage of the pets.
+    pub total: i64,
+    // Averaged over the active time, from the first to the last row processed.
+    pub per_second: f64,
+    // Most damage first, without the spells of the pets.
+    pub spells: Vec<SpellDamageDone>,
+    // Most damage first, empty unless pets are merged into their owners.
+    pub pets: Vec<PetDamageDone>,
+}
+
+// Adds up the spells of several units, most damage first.
+fn merge_spells(units: &[&HashMap<i64, SpellDamageDone>]) -> Vec<SpellDamageDone> {
+    let mut merged: HashMap<i64, SpellDamageDone> = HashMap::new();
+    for spell in units.iter().flat_map(|spells| spells.values()) {
+        match merged.get_mut(&spell.spell_id) {
+            Some(total) => {
+                total.total += spell.total;
+                total.hits += spell.hits;
+                total.crits += spell.crits;
+                total.max_hit = total.max_hit.max(spell.max_hit);
+            }
+            None => {
+                merged.insert(spell.spell_id, spell.clone());
+            }
+        }
+    }
+
+    let mut spells: Vec<SpellDamageDone> = merged.into_values().collect();
+    spells.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+    spells
+}
+
+// Damage one unit did to another, with the spell it was done with.
+pub(super) struct Hit<'a> {
+    pub source_guid: &'a Guid,
+    pub source_name: &'a LogCell<'a>,
+    pub dest_guid: &'a Guid,
+    pub dest_name: &'a LogCell<'a>,
+    pub hostile: bool,
+    pub spell_id: i64,
+    pub spell_name: &'a str,
+    pub school: SpellSchool,
+    // The damage that got through, and the damage a shield absorbed.
+    pub amount: i64,
+    pub absorbed: i64,
+    pub critical: bool,
+    // False for the part of a hit a shield absorbed, which gets a row of its own
+    // next to the row of the hit.
+    pub landed: bool,
+}
+
+fn is_absorb(miss_type: &LogCell) -> bool {
+    miss_type.as_str() == Some("ABSORB")
+}
+
+pub(super) fn hit_of<'a>(row: &'a LogRow) -> Option<Hit<'a>> {
+    match row {
+        LogRow::SpellDamage(damage)
+        | LogRow::SpellPeriodicDamage(damage)
+        | LogRow::RangeDamage(damage) => Some(Hit {
+            source_guid: &damage.sourceGUID,
+            source_name: &damage.sourceName,
+            dest_guid: &damage.destGUID,
+            dest_name: &damage.destName,
+            hostile: !damage.destFlags.is_friendly(),
+            spell_id: damage.spellId.as_i64().unwrap_or_default(),
+            spell_name: damage.spellName.as_str().unwrap_or_default(),
+            school: damage.spellSchool,
+            amount: damage.amount.as_i64().unwrap_or_default(),
+            absorbed: 0,
+            critical: damage.critical,
+            landed: true,
+        }),
+        LogRow::SwingDamage(damage) => Some(Hit {
+            source_guid: &damage.sourceGUID,
+            source_name: &damage.sourceName,
+            dest_guid: &damage.destGUID,
+            dest_name: &damage.destName,
+            hostile: !damage.destFlags.is_friendly(),
+            spell_id: MELEE_SPELL_ID,
+            spell_name: MELEE_SPELL_NAME,
+            school: damage.school,
+            amount: damage.amount.as_i64().unwrap_or_default(),
+            absorbed: 0,
+            critical: damage.critical,
+            landed: true,
+        }),
+        // A hit that was absorbed in full. The damage is in the SPELL_ABSORBED row.
+        LogRow::SpellMissed(miss) | LogRow::SpellPeriodicMissed(miss)
+            if is_absorb(&miss.missType) =>
+        {
+            Some(Hit {
+                source_guid: &miss.sourceGUID,
+                source_name: &miss.sourceName,
+                dest_guid: &miss.destGUID,
+                dest_name: &miss.destName,
+                hostile: !miss.destFlags.is_friendly(),
+                spell_id: miss.spellId.as_i64().unwrap_or_default(),
+                spell_name: miss.spellName.as_str().unwrap_or_default(),
+                school: miss.spellSchool,
+                amount: 0,
+                absorbed: 0,
+                critical: miss.critical,
+                landed: true,
+            })
+        }
+        LogRow::SwingMissed(miss) if is_absorb(&miss.missType) => Some(Hit {
+            source_guid: &miss.sourceGUID,
+            source_name: &miss.sourceName,
+            dest_guid: &miss.destGUID,
+            dest_name: &miss.destName,
+            hostile: !miss.destFlags.is_friendly(),
+            spell_id: MELEE_SPELL_ID,
+            spell_name: MELEE_SPELL_NAME,
+            school: SpellSchool::PHYSICAL,
+            amount: 0,
+            absorbed: 0,
+            critical: miss.critical,
+            landed: true,
+        }),
+        // Absorbed damage still counts against the attacker, the shield's caster
+        // is credited with it as healing.
+        LogRow::SpellAbsorbed(absorbed) => Some(Hit {
+            source_guid: &absorbed.sourceGUID,
+            source_name: &absorbed.sourceName,
+            dest_guid: &absorbed.destGUID,
+            dest_name: &absorbed.destName,
+            hostile: !absorbed.destFlags.is_friendly(),
+            spell_id: absorbed
+                .spellId
+                .as_ref()
+                .map_or(Some(MELEE_SPELL_ID), LogCell::as_i64)
+                .unwrap_or_default(),
+            spell_name: absorbed
+                .spellName
+                .as_ref()
+                .map_or(Some(MELEE_SPELL_NAME), LogCell::as_str)
+                .unwrap_or_default(),
+            school: absorbed.spellSchool.unwrap_or(SpellSchool::PHYSICAL),
+            amount: 0,
+            absorbed: absorbed.amount.as_i64().unwrap_or_default(),
+            critical: absorbed.critical,
+            landed: false,
+        }),
+        _ => None,
+    }
+}
+
+/// Adds up the damage every player and their pets did to hostile units in the
+/// rows passed to `process`, usually the rows of one pull. Damage absorbed by
+/// shields counts.
+#[derive(Default)]
+pub struct DamageMeter {
+    // The name and the spells of each unit, players and pets alike.
+    units: HashMap<Guid, (String, HashMap<i64, SpellDamageDone>)>,
+    owners: PetOwners,
+    merge_pets: bool,
+    start: Option<i64>,
+    end: i64,
+}
+
+impl DamageMeter {
+    /// With `merge_pets`, the damage of pets is added to their owners, otherwise
+    /// pets are listed on their own.
+    pub fn new(merge_pets: bool) -> Self {
+        Self {
+            merge_pets,
+            ..Self::default()
+        }
+    }
+
+    /// Like `new`, with the owners of the pets known before the first row
+    /// processed, e.g. from the rows before the pull.
+    pub fn with_owners(merge_pets: bool, owners: PetOwners) -> Self {
+        Self {
+            owners,
+            merge_pets,
+            ..Self::default()
+        }
+    }
+
+    pub fn process(&mut self, timestamp: i64, row: &LogRow) {
+        self.start.get_or_insert(timestamp);
+        self.end = timestamp;
+        self.owners.process(row);
+
+        let Some(hit) = hit_of(row).filter(|hit| hit.hostile) else {
+            return;
+        };
+        let (_, spells) = self
+            .units
+            .entry(hit.source_guid.clone())
+            .or_insert_with(|| {
+                (
+                    hit.source_name.as_str().unwrap_or_default().to_string(),
+                    HashMap::new(),
+                )
+            });
+        let spell = spells
+            .entry(hit.spell_id)
+            .or_insert_with(|| SpellDamageDone {
+                spell_id: hit.spell_id,
+                name: hit.spell_name.to_string(),
+                school: hit.school,
+                total: 0,
+                hits: 0,
+                crits: 0,
+                max_hit: 0,
+            });
+        spell.total += hit.amount + hit.absorbed;
+        if hit.landed {
+            spell.hits += 1;
+            spell.crits += hit.critical as u32;
+            spell.max_hit = spell.max_hit.max(hit.amount);
+        }
+    }
+
+    /// Milliseconds from the first to the last row processed.
+    pub fn active_time(&self) -> i64 {
+        self.start.map_or(0, |start| self.end - start)
+    }
+
+    /// The damage done by each player, and by each pet unless merged, most first.
+    pub fn entries(&self) -> Vec<DamageDone> {
+        let seconds = (self.active_time() as f64 / 1000.0).max(1.0);
+        let mut entries: Vec<DamageDone> = self
+            .owners
+            .group(&self.units, self.merge_pets)
+            .into_iter()
+            .map(|listing| {
+                let spells = merge_spells(&listing.spells);
+                let mut pets: Vec<PetDamageDone> = listing
+                    .pets
+                    .into_iter()
+                    .map(|(name, units)| {
+                        let spells = merge_spells(&units);
+                        PetDamageDone {
+                            name,
+                            total: spells.iter().map(|spell| spell.total).sum(),
+                            spells,
+                        }
+                    })
+                    .collect();
+                pets.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+                let total = spells.iter().map(|spell| spell.total).sum::<i64>()
+                    + pets.iter().map(|pet| pet.total).sum::<i64>();
+
+                DamageDone {
+                    guid: listing.guid,
+                    name: listing.name,
+                    owner: listing.owner,
+                    total,
+                    per_second: total as f64 / seconds,
+                    spells,
+                    pets,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{EncounterSegmenter, LogFile, Parser};
+
+    #[test]
+    fn adds_up_damage_per_player_and_spell() {
+        let log = "4/29 20:15:00.000  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,81483954,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,40000,40000,-1,8,0,0,0,nil,nil,nil\n\
+            4/29 20:15:02.000  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,81483954,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,60000,30000,-1,8,0,0,0,1,nil,nil\n\
+            4/29 20:15:05.000  SWING_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,0000000000000000,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,20000,20000,-1,1,0,0,0,nil,nil,nil\n\
+            4/29 20:15:07.000  SWING_DAMAGE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,90000000,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,500000,500000,20000,1,0,0,0,nil,nil,nil\n\
+            4/29 20:15:10.000  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,213709,\"Brambles\",0x8,Player-1379-0A9FF58F,0000000000000000,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,1000,1000,-1,8,0,0,0,nil,nil,nil\n";
+
+        let mut meter = DamageMeter::new(true);
+        for event in Parser::rows(log.as_bytes()).flatten() {
+            meter.process(event.timestamp, &event.row);
+        }
+
+        assert_eq!(meter.active_time(), 10_000);
+        // Rashok is no player, and damage to friendly units does not count.
+        let entries = meter.entries();
+        assert_eq!(entries.len(), 1);
+        let player = &entries[0];
+        assert_eq!(player.name, "Yerrog-Sanguino");
+        assert_eq!(player.total, 120_000);
+        assert_eq!(player.per_second, 12_000.0);
+
+        assert_eq!(player.spells.len(), 2);
+        let brambles = &player.spells[0];
+        assert_eq!(brambles.name, "Brambles");
+        assert_eq!(brambles.school, SpellSchool::NATURE);
+        assert_eq!(brambles.hits, 2);
+        assert_eq!(brambles.crit_percent(), 50.0);
+        assert_eq!(brambles.average_hit(), 50_000);
+        assert_eq!(brambles.max_hit, 60_000);
+        assert_eq!(player.spells[1].name, MELEE_SPELL_NAME);
+        assert_eq!(player.spells[1].total, 20_000);
+    }
+
+    #[test]
+    fn merges_pets_into_their_owner() {
+        // Two wolves of the same hunter, one known from the advanced params of its
+        // swing and one from its summon.
+        let log = "4/29 20:15:00.000  SWING_DAMAGE,Pet-0-4252-2569-19964-165189-0102F3A5B1,\"Wolf\",0x1112,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Pet-0-4252-2569-19964-165189-0102F3A5B1,Player-1379-0A9FF58F,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,20000,20000,-1,1,0,0,0,nil,nil,nil\n\
+            4/29 20:15:01.000  SPELL_SUMMON,Player-1379-0A9FF58F,\"Hunter-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-165189-0102F3A5B2,\"Wolf\",0xa28,0x0,883,\"Call Pet 1\",0x1\n\
+            4/29 20:15:02.000  SWING_DAMAGE,Creature-0-4252-2569-19964-165189-0102F3A5B2,\"Wolf\",0x2112,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Creature-0-4252-2569-19964-165189-0102F3A5B2,0000000000000000,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,10000,10000,-1,1,0,0,0,1,nil,nil\n";
+        let entries = |merge_pets| {
+            let mut meter = DamageMeter::new(merge_pets);
+            for event in Parser::rows(log.as_bytes()).flatten() {
+                meter.process(event.timestamp, &event.row);
+            }
+            meter.entries()
+        };
+
+        // The hunter did nothing but through the wolves.
+        let merged = entries(true);
+        assert_eq!(merged.len(), 1);
+        let hunter = &merged[0];
+        assert_eq!(hunter.name, "Hunter-Sanguino");
+        assert_eq!(hunter.total, 30_000);
+        assert!(hunter.spells.is_empty());
+        assert_eq!(hunter.pets.len(), 1);
+        assert_eq!(hunter.pets[0].name, "Wolf");
+        assert_eq!(hunter.pets[0].spells[0].hits, 2);
+        assert_eq!(hunter.pets[0].spells[0].crits, 1);
+
+        let listed = entries(false);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "Wolf");
+        assert_eq!(listed[0].owner.as_deref(), Some("Hunter-Sanguino"));
+        assert_eq!(listed[0].total, 30_000);
+    }
+
+    #[test]
+    fn merges_pets_summoned_before_the_pull() {
+        let path = std::env::temp_dir().join(format!(
+            "wow-log-analyzer-summon-{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            "4/29 20:14:00.000  SPELL_SUMMON,Player-1379-0A9FF58F,\"Hunter-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-165189-0102F3A5B2,\"Wolf\",0xa28,0x0,883,\"Call Pet 1\",0x1\n\
+            4/29 20:15:00.000  ENCOUNTER_START,2680,\"Rashok, the Elder\",16,20,2569\n\
+            4/29 20:15:02.000  SWING_DAMAGE,Creature-0-4252-2569-19964-165189-0102F3A5B2,\"Wolf\",0x2112,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Creature-0-4252-2569-19964-165189-0102F3A5B2,0000000000000000,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,10000,10000,-1,1,0,0,0,1,nil,nil\n\
+            4/29 20:15:30.000  ENCOUNTER_END,2680,\"Rashok, the Elder\",16,20,1,30000\n",
+        )
+        .unwrap();
+
+        let log = LogFile::open(&path).unwrap();
+        let mut segmenter = EncounterSegmenter::new();
+        for event in log.rows().filter_map(Result::ok) {
+            segmenter.push(event.line - 1, event.offset, event.timestamp, &event.row);
+        }
+        let encounters = segmenter.finish();
+        let entries = Parser::analyze_encounter(
+            &path,
+            &encounters[0],
+            PetOwners::from_events,
+            |owners, events| {
+                let mut meter = DamageMeter::with_owners(true, owners);
+                for event in events {
+                    meter.process(event.timestamp, &event.row);
+                }
+                meter.entries()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "Hunter-Sanguino");
+        assert_eq!(entries[0].total, 10_000);
+        assert_eq!(entries[0].pets[0].name, "Wolf");
+
+        drop(log);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lists_pets_of_unknown_owners_on_their_own() {
+        // The hunter never shows up in the log, only in the advanced params of the wolf.
+        let log = "4/29 20:15:00.000  SWING_DAMAGE,Pet-0-4252-2569-19964-165189-0102F3A5B1,\"Wolf\",0x1112,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Pet-0-4252-2569-19964-165189-0102F3A5B1,Player-1379-0A9FF58F,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,20000,20000,-1,1,0,0,0,nil,nil,nil\n";
+
+        let mut meter = DamageMeter::new(true);
+        for event in Parser::rows(log.as_bytes()).flatten() {
+            meter.process(event.timestamp, &event.row);
+        }
+
+        let entries = meter.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "Wolf");
+        assert_eq!(entries[0].owner, None);
+        assert_eq!(entries[0].total, 20_000);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::parser::{Guid, LogAdvancedParams, LogCell, LogRow};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DeathRecapKind {
+    Damage,
+    Heal,
+}
+
+/// A single damage or heal taken by a unit shortly before it died.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeathRecapEntry {
+    // Milliseconds, on the same clock as the timestamps passed to `process`.
+    pub timestamp: i64,
+    pub kind: DeathRecapKind,
+    pub source_name: String,
+    pub spell_name: String,
+    pub amount: i64,
+    // Overkill for damage, overhealing for heals.
+    pub wasted: i64,
+    // The unit's health after the event, when advanced logging recorded it.
+    pub hp: Option<i64>,
+    pub max_hp: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeathRecap {
+    pub timestamp: i64,
+    pub unit_guid: Guid,
+    pub unit_name: String,
+    // Oldest first, ending with the killing blow.
+    pub events: Vec<DeathRecapEntry>,
+}
+
+/// Builds a recap of the damage and healing each unit took in the last
+/// `window` milliseconds before a UNIT_DIED row.
+pub struct DeathRecaps {
+    window: i64,
+    recent: HashMap<Guid, VecDeque<DeathRecapEntry>>,
+    deaths: Vec<DeathRecap>,
+}
+
+fn cell_string(cell: &LogCell) -> String {
+    cell.as_str().unwrap_or_default().to_string()
+}
+
+// The advanced params only describe the unit we care about when the row is about it.
+fn hp_of(advanced: &Option<LogAdvancedParams>, unit_guid: &Guid) -> (Option<i64>, Option<i64>) {
+    match advanced {
+        Some(advanced) if advanced.unitGUID == *unit_guid => {
+            (advanced.currHp.as_i64(), advanced.maxHp.as_i64())
+        }
+        _ => (None, None),
+    }
+}
+
+impl DeathRecaps {
+    pub fn new(window: i64) -> Self {
+        Self {
+            window,
+            recent: HashMap::new(),
+            deaths: Vec::new(),
+        }
+    }
+
+    /// How far back a recap goes, in milliseconds.
+    pub fn window(&self) -> i64 {
+        self.window
+    }
+
+    pub fn process(&mut self, timestamp: i64, row: &LogRow) {
+        let (unit_guid, entry) = match row {
+            LogRow::SpellDamage(damage)
+            | LogRow::SpellPeriodicDamage(damage)
+            | LogRow::RangeDamage(damage) => {
+                let (hp, max_hp) = hp_of(&damage.advanced, &damage.destGUID);
+                (
+                    &damage.destGUID,
+                    DeathRecapEntry {
+                        timestamp,
+                        kind: DeathRecapKind::Damage,
+                        source_name: cell_string(&damage.sourceName),
+                        spell_name: cell_string(&damage.spellName),
+                        amount: damage.amount.as_i64().unwrap_or_default(),
+                        wasted: damage.overkill.as_i64().unwrap_or_default().max(0),
+                        hp,
+                        max_hp,
+                    },
+                )
+            }
+            LogRow::SwingDamage(damage) => {
+                let (hp, max_hp) = hp_of(&damage.advanced, &damage.destGUID);
+                (
+                    &damage.destGUID,
+                    DeathRecapEntry {
+                        timestamp,
+                        kind: DeathRecapKind::Damage,
+                        source_name: cell_string(&damage.sourceName),
+                        spell_name: "Melee".to_string(),
+                        amount: damage.amount.as_i64().unwrap_or_default(),
+                        wasted: damage.overkill.as_i64().unwrap_or_default().max(0),
+                        hp,
+                        max_hp,
+                    },
+                )
+            }
+            LogRow::SpellHeal(heal) | LogRow::SpellPeriodicHeal(heal) => {
+                let (hp, max_hp) = hp_of(&heal.advanced, &heal.destGUID);
+                (
+                    &heal.destGUID,
+                    DeathRecapEntry {
+                        timestamp,
+                        kind: DeathRecapKind::Heal,
+                        source_name: cell_string(&heal.sourceName),
+                        spell_name: cell_string(&heal.spellName),
+                        amount: heal.amount.as_i64().unwrap_or_default(),
+                        wasted: heal.overhealing.as_i64().unwrap_or_default(),
+                        hp,
+                        max_hp,
+                    },
+                )
+            }
+            LogRow::UnitDied(died) => {
+                self.record_death(timestamp, died.destGUID.clone(), &died.destName);
+                return;
+            }
+            _ => return,
+        };
+
+        let recent = self.recent.entry(unit_guid.clone()).or_default();
+        recent.push_back(entry);
+        while recent
+            .front()
+            .is_some_and(|entry| timestamp - entry.timestamp > self.window)
+        {
+            recent.pop_front();
+        }
+    }
+
+    fn record_death(&mut self, timestamp: i64, unit_guid: Guid, unit_name: &LogCell) {
+        let events = self
+            .recent
+            .remove(&unit_guid)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| timestamp - entry.timestamp <= self.window)
+            .collect();
+
+        self.deaths.push(DeathRecap {
+            timestamp,
+            unit_guid,
+            unit_name: cell_string(unit_name),
+            events,
+        });
+    }
+
+    /// The deaths recorded so far, oldest first.
+    pub fn deaths(&self) -> &[DeathRecap] {
+        &self.deaths
+    }
+
+    pub fn finish(self) -> Vec<DeathRecap> {
+        self.deaths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_log_csv, LogLayout};
+
+    #[test]
+    fn recaps_last_seconds_before_death() {
+        let rows = [
+            (0, "SPELL_DAMAGE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405819,\"Searing Slam\",0x4,Player-1379-0A9FF58F,0000000000000000,400000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,100000,100000,-1,4,0,0,0,nil,nil,nil"),
+            (8000, "SPELL_HEAL,Player-1379-0B0D2C10,\"Healer-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,2061,\"Flash Heal\",0x2,Player-1379-0A9FF58F,0000000000000000,450000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,50000,50000,0,0,nil"),
+            (9000, "SWING_DAMAGE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,90000000,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,300000,300000,-1,1,0,0,0,nil,nil,nil"),
+            (9500, "SPELL_DAMAGE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405819,\"Searing Slam\",0x4,Player-1379-0A9FF58F,0000000000000000,0,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,200000,200000,50000,4,0,0,0,nil,nil,nil"),
+            (9500, "UNIT_DIED,0000000000000000,nil,0x80000000,0x80000000,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,0"),
+        ];
+
+        let mut recaps = DeathRecaps::new(5000);
+        for (timestamp, line) in rows {
+            let (_, row) = parse_log_csv(line, &LogLayout::default()).unwrap();
+            recaps.process(timestamp, &row);
+        }
+        let deaths = recaps.finish();
+
+        assert_eq!(deaths.len(), 1);
+        let death = &deaths[0];
+        assert_eq!(death.unit_name, "Yerrog-Sanguino");
+        assert_eq!(death.events.len(), 3);
+        assert_eq!(death.events[0].kind, DeathRecapKind::Heal);
+        assert_eq!(death.events[0].hp, Some(450000));
+        // Advanced params of a melee swing describe the attacker, not the victim.
+        assert_eq!(death.events[1].spell_name, "Melee");
+        assert_eq!(death.events[1].hp, None);
+        assert_eq!(death.events[2].wasted, 50000);
+        assert_eq!(death.events[2].hp, Some(0));
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use super::pets::PetOwners;
+use crate::parser::{Guid, LogCell, LogRow, SpellSchool};
+
+/// The healing done with one spell, as part of a `HealingDone`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpellHealingDone {
+    pub spell_id: i64,
+    pub name: String,
+    pub school: SpellSchool,
+    // Without overhealing.
+    pub healing: i64,
+    pub overhealing: i64,
+    pub hits: u32,
+    pub crits: u32,
+    pub max_hit: i64,
+}
+
+impl SpellHealingDone {
+    pub fn overheal_percent(&self) -> f64 {
+        overheal_percent(self.healing, self.overhealing)
+    }
+
+    pub fn crit_percent(&self) -> f64 {
+        self.crits as f64 * 100.0 / self.hits.max(1) as f64
+    }
+}
+
+/// The healing the pets of one name did, as part of a `HealingDone`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PetHealingDone {
+    pub name: String,
+    pub healing: i64,
+    pub overhealing: i64,
+    // Most healing first.
+    pub spells: Vec<SpellHealingDone>,
+}
+
+/// The healing a player did, or a pet when pets are not merged into their
+/// owners, from `HealingMeter::entries`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct HealingDone {
+    pub guid: Guid,
+    pub name: String,
+    // The name of the player that owns the pet.
+    pub owner: Option<String>,
+    // Without overhealing, including the damage the player's shields absorbed
+    // and the healing of the pets.
+    pub healing: i64,
+    pub overhealing: i64,
+    // Averaged over the active time, from the first to the last row processed.
+    pub per_second: f64,
+    // Most healing first, without the spells of the pets.
+    pub spells: Vec<SpellHealingDone>,
+    // Most healing first, empty unless pets are merged into their owners.
+    pub pets: Vec<PetHealingDone>,
+}
+
+impl HealingDone {
+    pub fn overheal_percent(&self) -> f64 {
+        overheal_percent(self.healing, self.overhealing)
+    }
+}
+
+// Adds up the spells of several units, most healing first.
+fn merge_spells(units: &[&HashMap<i64, SpellHealingDone>]) -> Vec<SpellHealingDone> {
+    let mut merged: HashMap<i64, SpellHealingDone> = HashMap::new();
+    for spell in units.iter().flat_map(|spells| spells.values()) {
+        match merged.get_mut(&spell.spell_id) {
+            Some(total) => {
+                total.healing += spell.healing;
+                total.overhealing += spell.overhealing;
+                total.hits += spell.hits;
+                total.crits += spell.crits;
+                total.max_hit = total.max_hit.max(spell.max_hit);
+            }
+            None => {
+                merged.insert(spell.spell_id, spell.clone());
+            }
+        }
+    }
+
+    let mut spells: Vec<SpellHealingDone> = merged.into_values().collect();
+    spells.sort_by(|a, b| b.healing.cmp(&a.healing).then_with(|| a.name.cmp(&b.name)));
+    spells
+}
+
+fn overheal_percent(healing: i64, overhealing: i64) -> f64 {
+    overhealing as f64 * 100.0 / (healing + overhealing).max(1) as f64
+}
+
+// A heal, or damage absorbed by a shield, with the spell it was done with.
+struct Heal<'a> {
+    source_guid: &'a Guid,
+    source_name: &'a LogCell<'a>,
+    spell_id: i64,
+    spell_name: &'a str,
+    school: SpellSchool,
+    healing: i64,
+    overhealing: i64,
+    critical: bool,
+}
+
+fn heal_of<'a>(row: &'a LogRow) -> Option<Heal<'a>> {
+    match row {
+        LogRow::SpellHeal(heal) | LogRow::SpellPeriodicHeal(heal) => {
+            let amount = heal.amount.as_i64().unwrap_or_default();
+            let overhealing = heal.overhealing.as_i64().unwrap_or_default();
+            Some(Heal {
+                source_guid: &heal.sourceGUID,
+                source_name: &heal.sourceName,
+                spell_id: heal.spellId.as_i64().unwrap_or_default(),
+                spell_name: heal.spellName.as_str().unwrap_or_default(),
+                school: heal.spellSchool,
+                healing: amount - overhealing,
+                overhealing,
+                critical: heal.critical,
+            })
+        }
+        // Shields are credited to the unit that cast them, not to the attacker.
+        LogRow::SpellAbsorbed(absorbed) => Some(Heal {
+            source_guid: &absorbed.casterGUID,
+            source_name: &absorbed.casterName,
+            spell_id: absorbed.absorbSpellId.as_i64().unwrap_or_default(),
+            spell_name: absorbed.absorbSpellName.as_str().unwrap_or_default(),
+            school: absorbed.absorbSpellSchool,
+            healing: absorbed.amount.as_i64().unwrap_or_default(),
+            overhealing: 0,
+            critical: absorbed.critical,
+        }),
+        _ => None,
+    }
+}
+
+/// Adds up the healing every player and their pets did in the rows passed to
+/// `process`, usually the rows of one pull. Damage absorbed by a shield counts
+/// as healing done by the unit who cast it.
+#[derive(Default)]
+pub struct HealingMeter {
+    // The name and the spells of each unit, players and pets alike.
+    units: HashMap<Guid, (String, HashMap<i64, SpellHealingDone>)>,
+    owners: PetOwners,
+    merge_pets: bool,
+    start: Option<i64>,
+    end: i64,
+}
+
+impl HealingMeter {
+    /// With `merge_pets`, the healing of pets is added to their owners, otherwise
+    /// pets are listed on their own.
+    pub fn new(merge_pets: bool) -> Self {
+        Self {
+            merge_pets,
+            ..Self::default()
+        }
+    }
+
+    /// Like `new`, with the owners of the pets known before the first row
+    /// processed, e.g. from the rows before the pull.
+    pub fn with_owners(merge_pets: bool, owners: PetOwners) -> Self {
+        Self {
+            owners,
+            merge_pets,
+            ..Self::default()
+        }
+    }
+
+    pub fn process(&mut self, timestamp: i64, row: &LogRow) {
+        self.start.get_or_insert(timestamp);
+        self.end = timestamp;
+        self.owners.process(row);
+
+        let Some(heal) = heal_of(row) else {
+            return;
+        };
+        let (_, spells) = self
+            .units
+            .entry(heal.source_guid.clone())
+            .or_insert_with(|| {
+                (
+                    heal.source_name.as_str().unwrap_or_default().to_string(),
+                    HashMap::new(),
+                )
+            });
+        let spell = spells
+            .entry(heal.spell_id)
+            .or_insert_with(|| SpellHealingDone {
+                spell_id: heal.spell_id,
+                name: heal.spell_name.to_string(),
+                school: heal.school,
+                healing: 0,
+                overhealing: 0,
+                hits: 0,
+                crits: 0,
+                max_hit: 0,
+            });
+        spell.healing += heal.healing;
+        spell.overhealing += heal.overhealing;
+        spell.hits += 1;
+        spell.crits += heal.critical as u32;
+        spell.max_hit = spell.max_hit.max(heal.healing);
+    }
+
+    /// Milliseconds from the first to the last row processed.
+    pub fn active_time(&self) -> i64 {
+        self.start.map_or(0, |start| self.end - start)
+    }
+
+    /// The healing done by each player, and by each pet unless merged, most first.
+    pub fn entries(&self) -> Vec<HealingDone> {
+        let seconds = (self.active_time() as f64 / 1000.0).max(1.0);
+        let mut entries: Vec<HealingDone> = self
+            .owners
+            .group(&self.units, self.merge_pets)
+            .into_iter()
+            .map(|listing| {
+                let spells = merge_spells(&listing.spells);
+                let mut pets: Vec<PetHealingDone> = listing
+                    .pets
+                    .into_iter()
+                    .map(|(name, units)| {
+                        let spells = merge_spells(&units);
+                        PetHealingDone {
+                            name,
+                            healing: spells.iter().map(|spell| spell.healing).sum(),
+                            overhealing: spells.iter().map(|spell| spell.overhealing).sum(),
+                            spells,
+                        }
+                    })
+                    .collect();
+                pets.sort_by(|a, b| b.healing.cmp(&a.healing).then_with(|| a.name.cmp(&b.name)));
+                let healing = spells.iter().map(|spell| spell.healing).sum::<i64>()
+                    + pets.iter().map(|pet| pet.healing).sum::<i64>();
+                let overhealing = spells.iter().map(|spell| spell.overhealing).sum::<i64>()
+                    + pets.iter().map(|pet| pet.overhealing).sum::<i64>();
+
+                HealingDone {
+                    guid: listing.guid,
+                    name: listing.name,
+                    owner: listing.owner,
+                    healing,
+                    overhealing,
+                    per_second: healing as f64 / seconds,
+                    spells,
+                    pets,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.healing.cmp(&a.healing).then_with(|| a.name.cmp(&b.name)));
+
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn adds_up_heals() {
+        let log = "4/29 20:15:00.000  SPELL_HEAL,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,22842,\"Frenzied Regeneration\",0x1,Player-1379-0A9FF58F,0000000000000000,1483954,1952835,9102,1200,5043,0,1,60,100,0,-5095.52,1142.47,2073,6.1556,70,31204,40117,8913,0,nil\n\
+            4/29 20:15:02.000  SPELL_PERIODIC_HEAL,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,22842,\"Frenzied Regeneration\",0x1,Player-1379-0A9FF58F,0000000000000000,1483954,1952835,9102,1200,5043,0,1,60,100,0,-5095.52,1142.47,2073,6.1556,70,10000,10000,10000,0,1\n";
+
+        let mut meter = HealingMeter::new(true);
+        for event in Parser::rows(log.as_bytes()).flatten() {
+            meter.process(event.timestamp, &event.row);
+        }
+
+        let entries = meter.entries();
+        assert_eq!(entries.len(), 1);
+        let druid = &entries[0];
+        assert_eq!(druid.healing, 22_291);
+        assert_eq!(druid.overhealing, 18_913);
+        assert_eq!(druid.per_second, 11_145.5);
+        let regeneration = &druid.spells[0];
+        assert_eq!(regeneration.hits, 2);
+        assert_eq!(regeneration.crit_percent(), 50.0);
+        assert_eq!(regeneration.max_hit, 22_291);
+    }
+
+    #[test]
+    fn credits_shields_to_their_caster() {
+        // A spell and a melee swing absorbed by the priest's shield on the druid.
+        let log = "4/29 20:15:05.000  SPELL_ABSORBED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405316,\"Ancient Fury\",0x4,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,17,\"Power Word: Shield\",0x2,52345,80000,nil\n\
+            4/29 20:15:10.000  SPELL_ABSORBED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,17,\"Power Word: Shield\",0x2,20000,25000,nil\n";
+
+        let mut meter = HealingMeter::new(true);
+        for event in Parser::rows(log.as_bytes()).flatten() {
+            meter.process(event.timestamp, &event.row);
+        }
+
+        // Neither the attacker nor the shielded player gets credit for the absorbs.
+        let entries = meter.entries();
+        assert_eq!(entries.len(), 1);
+        let priest = &entries[0];
+        assert_eq!(priest.name, "Priest-Sanguino");
+        assert_eq!(priest.healing, 72_345);
+        assert_eq!(priest.overheal_percent(), 0.0);
+        assert_eq!(priest.per_second, 14_469.0);
+        assert_eq!(priest.spells.len(), 1);
+        assert_eq!(priest.spells[0].name, "Power Word: Shield");
+        assert_eq!(priest.spells[0].hits, 2);
+    }
+}
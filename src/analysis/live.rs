@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::{Encounter, EncounterSegmenter, Event, Guid, LogRow, ParseError};
+
+use super::damage::hit_of;
+use super::death::{DeathRecap, DeathRecaps};
+use super::pets::PetOwners;
+
+/// The damage a player did in the pull shown by the live meter, or a pet when
+/// pets are not merged into their owners.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LiveMeterEntry {
+    pub name: String,
+    pub amount: i64,
+    // Averaged over the pull so far.
+    pub per_second: i64,
+}
+
+/// A log that is followed while it is written. Keeps the pulls so far, a damage
+/// meter of the raid and the deaths of its players, both for the pull in progress
+/// or, between pulls, for the last one.
+pub struct LiveLog {
+    segmenter: EncounterSegmenter,
+    deaths: DeathRecaps,
+    // The names of the players in the raid, everyone in the group when empty.
+    raid: HashSet<String>,
+    owners: PetOwners,
+    merge_pets: bool,
+    // The name, whether it was in the group and the damage of each unit.
+    damage: HashMap<Guid, (String, bool, i64)>,
+    // Milliseconds since the start of the log.
+    pull_start: i64,
+    pull_end: Option<i64>,
+    last_timestamp: i64,
+    // Index in `deaths` of the first death of the pull, and of the first one
+    // after it once it ended.
+    pull_deaths: usize,
+    pull_deaths_end: Option<usize>,
+    errors: usize,
+}
+
+impl LiveLog {
+    /// `death_recap_window` is in milliseconds. With `merge_pets`, the damage of
+    /// pets is added to their owners.
+    pub fn new(death_recap_window: i64, raid: HashSet<String>, merge_pets: bool) -> Self {
+        Self {
+            segmenter: EncounterSegmenter::new(),
+            deaths: DeathRecaps::new(death_recap_window),
+            raid,
+            owners: PetOwners::new(),
+            merge_pets,
+            damage: HashMap::new(),
+            pull_start: 0,
+            pull_end: None,
+            last_timestamp: 0,
+            pull_deaths: 0,
+            pull_deaths_end: None,
+            errors: 0,
+        }
+    }
+
+    /// Forgets the pulls, damage and deaths read so far, for a log that was
+    /// started over.
+    pub fn clear(&mut self) {
+        let raid = std::mem::take(&mut self.raid);
+        *self = Self::new(self.deaths.window(), raid, self.merge_pets);
+    }
+
+    fn in_raid(&self, name: &str, in_group: bool) -> bool {
+        if self.raid.is_empty() {
+            in_group
+        } else {
+            self.raid.contains(name)
+        }
+    }
+
+    pub fn push(&mut self, row: Result<Event, ParseError>) {
+        let event = match row {
+            Ok(event) => event,
+            Err(_) => {
+                self.errors += 1;
+                return;
+            }
+        };
+
+        self.segmenter
+            .push(event.line - 1, event.offset, event.timestamp, &event.row);
+        self.deaths.process(event.timestamp, &event.row);
+        self.owners.process(&event.row);
+        self.last_timestamp = event.timestamp;
+
+        match &event.row {
+            LogRow::EncounterStart(_) => {
+                self.damage.clear();
+                self.pull_start = event.timestamp;
+                self.pull_end = None;
+                self.pull_deaths = self.deaths.deaths().len();
+                self.pull_deaths_end = None;
+            }
+            LogRow::EncounterEnd(_) => {
+                self.pull_end = Some(event.timestamp);
+                self.pull_deaths_end = Some(self.deaths.deaths().len());
+            }
+            // Between pulls the meter keeps showing the last one.
+            row if self.pull_end.is_none() => {
+                let in_group = row.source_flags().is_some_and(|flags| flags.is_in_group());
+                if let Some(hit) = hit_of(row).filter(|hit| hit.hostile) {
+                    let (_, _, damage) =
+                        self.damage
+                            .entry(hit.source_guid.clone())
+                            .or_insert_with(|| {
+                                let name = hit.source_name.as_str().unwrap_or_default();
+                                (name.to_string(), in_group, 0)
+                            });
+                    *damage += hit.amount + hit.absorbed;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Every pull so far, including the one in progress.
+    pub fn encounters(&self) -> impl Iterator<Item = &Encounter> {
+        self.segmenter
+            .encounters()
+            .iter()
+            .chain(self.segmenter.current())
+    }
+
+    /// The damage done by the raid, most first.
+    pub fn meter(&self) -> Vec<LiveMeterEntry> {
+        let seconds = (self.pull_end.unwrap_or(self.last_timestamp) - self.pull_start) / 1000;
+        let mut meter: Vec<LiveMeterEntry> = Vec::new();
+        for (guid, (name, in_group, amount)) in &self.damage {
+            // Pets whose owner's name is unknown are listed on their own.
+            let owner = self
+                .owners
+                .player_of(guid)
+                .filter(|player| self.merge_pets && *player != guid)
+                .and_then(|player| match self.damage.get(player) {
+                    Some((name, _, _)) => Some(name.as_str()),
+                    None => self.owners.name_of(player),
+                });
+            let name = owner.unwrap_or(name);
+            if !self.in_raid(name, *in_group) {
+                continue;
+            }
+            match meter.iter_mut().find(|entry| entry.name == name) {
+                Some(entry) => entry.amount += amount,
+                None => meter.push(LiveMeterEntry {
+                    name: name.to_string(),
+                    amount: *amount,
+                    per_second: 0,
+                }),
+            }
+        }
+        for entry in &mut meter {
+            entry.per_second = entry.amount / seconds.max(1);
+        }
+        meter.sort_by(|a, b| b.amount.cmp(&a.amount).then_with(|| a.name.cmp(&b.name)));
+
+        meter
+    }
+
+    /// The players of the raid that died, oldest first.
+    pub fn deaths(&self) -> impl Iterator<Item = &DeathRecap> {
+        let deaths = self.deaths.deaths();
+        deaths[self.pull_deaths..self.pull_deaths_end.unwrap_or(deaths.len())]
+            .iter()
+            .filter(|death| death.unit_guid.is_player())
+            .filter(|death| self.raid.is_empty() || self.raid.contains(&death.unit_name))
+    }
+
+    /// The number of lines that could not be parsed.
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn follows_current_pull() {
+        let log = "4/29 20:15:00.000  SWING_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,0000000000000000,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,9999,9999,-1,1,0,0,0,nil,nil,nil\n\
+            4/29 20:15:03.000  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n\
+            4/29 20:15:05.000  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,81483954,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,40000,40000,-1,8,0,0,0,nil,nil,nil\n\
+            4/29 20:15:06.000  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,213709,\"Brambles\",0x8,Player-1379-0A9FF58F,0000000000000000,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,1000,1000,-1,8,0,0,0,nil,nil,nil\n\
+            4/29 20:15:07.000  SWING_DAMAGE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,90000000,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,500000,500000,20000,1,0,0,0,nil,nil,nil\n\
+            4/29 20:15:07.000  UNIT_DIED,0000000000000000,nil,0x80000000,0x80000000,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,0\n\
+            4/29 20:15:09.000  ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,0,6000\n\
+            4/29 20:15:11.000  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201321-000550239B,\"Trash\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2569-19964-201321-000550239B,0000000000000000,81483954,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,30000,30000,-1,8,0,0,0,nil,nil,nil\n\
+            4/29 20:15:12.000  UNIT_DIED,0000000000000000,nil,0x80000000,0x80000000,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,0\n\
+            4/29 20:15:13.000  SPELL_DAMAGE,broken\n";
+
+        let mut live = LiveLog::new(10_000, HashSet::new(), true);
+        for row in Parser::rows(log.as_bytes()) {
+            live.push(row);
+        }
+
+        assert_eq!(live.encounters().count(), 1);
+        assert_eq!(live.errors(), 1);
+        // Only damage done by the group to hostile units during the pull counts,
+        // after it ended the meter keeps showing it.
+        assert_eq!(
+            live.meter(),
+            vec![LiveMeterEntry {
+                name: "Yerrog-Sanguino".to_string(),
+                amount: 40000,
+                per_second: 6666,
+            }]
+        );
+        // The priest died to trash after the pull.
+        let deaths: Vec<_> = live.deaths().collect();
+        assert_eq!(deaths.len(), 1);
+        assert_eq!(deaths[0].events.last().unwrap().source_name, "Rashok");
+
+        // A log that was started over begins without pulls or deaths.
+        live.clear();
+        assert_eq!(live.encounters().count(), 0);
+        assert_eq!(live.meter(), vec![]);
+        assert_eq!(live.deaths().count(), 0);
+        assert_eq!(live.errors(), 0);
+        for row in Parser::rows(log.as_bytes()).take(3) {
+            live.push(row);
+        }
+        assert_eq!(live.encounters().count(), 1);
+        assert_eq!(live.meter()[0].amount, 40000);
+
+        // Only the players picked as the raid count, when there are any.
+        let mut live = LiveLog::new(10_000, HashSet::from(["Someone-Else".to_string()]), true);
+        for row in Parser::rows(log.as_bytes()) {
+            live.push(row);
+        }
+        assert_eq!(live.meter(), vec![]);
+        assert_eq!(live.deaths().count(), 0);
+    }
+}
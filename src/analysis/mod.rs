@@ -0,0 +1,15 @@
+mod damage;
+mod death;
+mod healing;
+mod live;
+mod pets;
+mod progress;
+mod taken;
+
+pub use self::damage::{DamageDone, DamageMeter, PetDamageDone, SpellDamageDone};
+pub use self::death::{DeathRecap, DeathRecapEntry, DeathRecapKind, DeathRecaps};
+pub use self::healing::{HealingDone, HealingMeter, PetHealingDone, SpellHealingDone};
+pub use self::live::{LiveLog, LiveMeterEntry};
+pub use self::pets::PetOwners;
+pub use self::progress::{boss_progress, difficulty_name, BossProgress};
+pub use self::taken::{AbilityDamageTaken, DamageTaken, DamageTakenMeter};
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::parser::{Event, Guid, LogRow};
+
+// Pets of pets, such as totems summoned by a guardian, are followed this many
+// steps up to their player.
+const MAX_OWNER_DEPTH: usize = 4;
+
+/// Which unit owns each pet, guardian, totem and created object, from the
+/// SPELL_SUMMON and SPELL_CREATE rows and the owner in the advanced params of
+/// the rows passed to `process`.
+#[derive(Default, Clone)]
+pub struct PetOwners {
+    owners: HashMap<Guid, Guid>,
+    // The names of the units that summoned something.
+    names: HashMap<Guid, String>,
+}
+
+// A player or a pet as listed in a meter, from `PetOwners::group`, with the
+// spells of every unit listed under it.
+pub(super) struct Listing<'a, S> {
+    pub guid: Guid,
+    pub name: String,
+    // The name of the owner, for pets listed on their own.
+    pub owner: Option<String>,
+    pub spells: Vec<&'a HashMap<i64, S>>,
+    // Pets merged into a player, by name.
+    pub pets: Vec<(String, Vec<&'a HashMap<i64, S>>)>,
+}
+
+impl PetOwners {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The owners known from `events`, e.g. from the rows before a pull, since
+    /// pets such as hunter pets and totems are often summoned before it starts.
+    pub fn from_events(events: &mut dyn Iterator<Item = Event<'_>>) -> Self {
+        let mut owners = Self::new();
+        for event in events {
+            owners.process(&event.row);
+        }
+        owners
+    }
+
+    pub fn process(&mut self, row: &LogRow) {
+        match row {
+            LogRow::SpellSummon(summon) | LogRow::SpellCreate(summon) => {
+                self.owners
+                    .insert(summon.destGUID.clone(), summon.sourceGUID.clone());
+                self.names
+                    .entry(summon.sourceGUID.clone())
+                    .or_insert_with(|| summon.sourceName.as_str().unwrap_or_default().to_string());
+            }
+            row => {
+                let Some(advanced) = row.advanced() else {
+                    return;
+                };
+                let (unit, owner) = (&advanced.unitGUID, &advanced.ownerGUID);
+                let owned = !matches!(owner, Guid::Nil | Guid::Unknown(_)) && owner != unit;
+                if owned && self.owners.get(unit) != Some(owner) {
+                    self.owners.insert(unit.clone(), owner.clone());
+                }
+            }
+        }
+    }
+
+    /// The unit that summoned `guid`, if known.
+    pub fn owner_of(&self, guid: &Guid) -> Option<&Guid> {
+        self.owners.get(guid)
+    }
+
+    /// The player `guid` belongs to: itself for a player, the player at the end
+    /// of its owners for a pet, or `None` for units no player owns.
+    pub fn player_of<'a>(&'a self, guid: &'a Guid) -> Option<&'a Guid> {
+        let mut unit = guid;
+        for _ in 0..=MAX_OWNER_DEPTH {
+            if unit.is_player() {
+                return Some(unit);
+            }
+            unit = self.owner_of(unit)?;
+        }
+        None
+    }
+
+    /// The name of a unit that summoned something.
+    pub fn name_of(&self, guid: &Guid) -> Option<&str> {
+        self.names.get(guid).map(String::as_str)
+    }
+
+    // Lists the players among `units`, with the pets of each merged into it when
+    // `merge_pets` is set, or listed on their own otherwise. Pets of the same
+    // name and owner are listed together, and pets whose owner's name is unknown
+    // on their own. Units no player owns are left out.
+    pub(super) fn group<'a, S>(
+        &self,
+        units: &'a HashMap<Guid, (String, HashMap<i64, S>)>,
+        merge_pets: bool,
+    ) -> Vec<Listing<'a, S>> {
+        let mut listings: Vec<Listing<S>> = units
+            .iter()
+            .filter(|(guid, _)| guid.is_player())
+            .map(|(guid, (name, spells))| Listing {
+                guid: guid.clone(),
+                name: name.clone(),
+                owner: None,
+                spells: vec![spells],
+                pets: Vec::new(),
+            })
+            .collect();
+
+        for (guid, (name, spells)) in units.iter().filter(|(guid, _)| !guid.is_player()) {
+            let Some(player) = self.player_of(guid) else {
+                continue;
+            };
+            let index = listings.iter().position(|listing| listing.guid == *player);
+            let owner_name = match index {
+                Some(index) => Some(listings[index].name.clone()),
+                None => self.name_of(player).map(str::to_string),
+            };
+
+            match owner_name {
+                Some(owner_name) if merge_pets => {
+                    let index = index.unwrap_or_else(|| {
+                        // The player did nothing but through its pets.
+                        listings.push(Listing {
+                            guid: player.clone(),
+                            name: owner_name,
+                            owner: None,
+                            spells: Vec::new(),
+                            pets: Vec::new(),
+                        });
+                        listings.len() - 1
+                    });
+                    let pets = &mut listings[index].pets;
+                    match pets.iter_mut().find(|(pet, _)| pet == name) {
+                        Some((_, pet_spells)) => pet_spells.push(spells),
+                        None => pets.push((name.clone(), vec![spells])),
+                    }
+                }
+                owner_name => {
+                    let pet = listings.iter_mut().find(|listing| {
+                        !listing.guid.is_player()
+                            && listing.name == *name
+                            && listing.owner == owner_name
+                    });
+                    match pet {
+                        Some(pet) => pet.spells.push(spells),
+                        None => listings.push(Listing {
+                            guid: guid.clone(),
+                            name: name.clone(),
+                            owner: owner_name,
+                            spells: vec![spells],
+                            pets: Vec::new(),
+                        }),
+                    }
+                }
+            }
+        }
+
+        listings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn resolves_owners_of_pets() {
+        // A hunter's pet, known from the advanced params of its swing, and a
+        // totem summoned by a guardian the shaman summoned.
+        let log = "4/29 20:15:00.000  SWING_DAMAGE,Pet-0-4252-2569-19964-165189-0102F3A5B1,\"Wolf\",0x1112,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Pet-0-4252-2569-19964-165189-0102F3A5B1,Player-1379-0A9FF58F,500000,500000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,20000,20000,-1,1,0,0,0,nil,nil,nil\n\
+            4/29 20:15:01.000  SPELL_SUMMON,Player-1379-0B1C2D3E,\"Shaman-Sanguino\",0x514,0x0,Creature-0-4252-2569-19964-95061-000550239B,\"Greater Fire Elemental\",0xa28,0x0,198067,\"Fire Elemental\",0x4\n\
+            4/29 20:15:02.000  SPELL_SUMMON,Creature-0-4252-2569-19964-95061-000550239B,\"Greater Fire Elemental\",0xa28,0x0,Creature-0-4252-2569-19964-61245-000550239C,\"Capacitor Totem\",0xa28,0x0,192058,\"Capacitor Totem\",0x8\n";
+
+        let mut owners = PetOwners::new();
+        for event in Parser::rows(log.as_bytes()).flatten() {
+            owners.process(&event.row);
+        }
+
+        let hunter = Guid::from("Player-1379-0A9FF58F");
+        let shaman = Guid::from("Player-1379-0B1C2D3E");
+        let wolf = Guid::from("Pet-0-4252-2569-19964-165189-0102F3A5B1");
+        let totem = Guid::from("Creature-0-4252-2569-19964-61245-000550239C");
+        assert_eq!(owners.player_of(&wolf), Some(&hunter));
+        assert_eq!(owners.player_of(&hunter), Some(&hunter));
+        assert_eq!(owners.player_of(&totem), Some(&shaman));
+        assert_eq!(owners.name_of(&shaman), Some("Shaman-Sanguino"));
+        assert_eq!(
+            owners.player_of(&Guid::from("Creature-0-4252-2569-19964-201320-000550239A")),
+            None
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::parser::Encounter;
+
+/// The pulls of one boss on one difficulty, from `boss_progress`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BossProgress {
+    pub encounter_id: i64,
+    pub name: String,
+    pub difficulty_id: i64,
+    // Indices of the pulls in the encounters passed to `boss_progress`, oldest first.
+    pub pulls: Vec<usize>,
+}
+
+/// Groups pulls by boss and difficulty, in the order each was first pulled.
+pub fn boss_progress(encounters: &[Encounter]) -> Vec<BossProgress> {
+    let mut bosses: Vec<BossProgress> = Vec::new();
+
+    for (index, encounter) in encounters.iter().enumerate() {
+        let boss = bosses.iter_mut().find(|boss| {
+            boss.encounter_id == encounter.id && boss.difficulty_id == encounter.difficulty_id
+        });
+        match boss {
+            Some(boss) => boss.pulls.push(index),
+            None => bosses.push(BossProgress {
+                encounter_id: encounter.id,
+                name: encounter.name.clone(),
+                difficulty_id: encounter.difficulty_id,
+                pulls: vec![index],
+            }),
+        }
+    }
+
+    bosses
+}
+
+/// The name of a difficulty id from ENCOUNTER_START, for raids and dungeons of
+/// retail and the classic clients.
+pub fn difficulty_name(difficulty_id: i64) -> &'static str {
+    match difficulty_id {
+        1 => "Normal",
+        2 => "Heroic",
+        3 | 175 => "10 Player",
+        4 | 176 => "25 Player",
+        5 | 193 => "10 Player (Heroic)",
+        6 | 194 => "25 Player (Heroic)",
+        7 | 17 => "Looking For Raid",
+        8 => "Mythic Keystone",
+        9 => "40 Player",
+        14 => "Normal",
+        15 => "Heroic",
+        16 | 23 => "Mythic",
+        148 => "20 Player",
+        _ => "Unknown difficulty",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::LogLayout;
+
+    fn pull(id: i64, name: &str, difficulty_id: i64) -> Encounter {
+        Encounter {
+            id,
+            name: name.to_string(),
+            difficulty_id,
+            group_size: 20,
+            instance_id: 2569,
+            success: false,
+            fight_time: 0,
+            start_time: 0,
+            end_time: 0,
+            rows: 0..0,
+            offset: 0,
+            layout: LogLayout::default(),
+            boss_health: None,
+        }
+    }
+
+    #[test]
+    fn groups_pulls_by_boss_and_difficulty() {
+        let encounters = [
+            pull(2688, "Rashok, the Elder", 15),
+            pull(2688, "Rashok, the Elder", 16),
+            pull(2680, "Magmorax", 16),
+            pull(2688, "Rashok, the Elder", 16),
+        ];
+
+        let bosses = boss_progress(&encounters);
+        assert_eq!(bosses.len(), 3);
+        assert_eq!(bosses[0].pulls, vec![0]);
+        assert_eq!(difficulty_name(bosses[0].difficulty_id), "Heroic");
+        assert_eq!(bosses[1].pulls, vec![1, 3]);
+        assert_eq!(difficulty_name(bosses[1].difficulty_id), "Mythic");
+        assert_eq!(bosses[2].name, "Magmorax");
+        assert_eq!(bosses[2].pulls, vec![2]);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use super::damage::hit_of;
+use crate::parser::{Guid, LogRow, SpellSchool};
+
+/// The damage taken from one ability, as part of a `DamageTaken`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AbilityDamageTaken {
+    pub spell_id: i64,
+    pub name: String,
+    pub school: SpellSchool,
+    // Including the damage shields absorbed.
+    pub total: i64,
+    pub absorbed: i64,
+    pub hits: u32,
+}
+
+/// The damage a player took, from `DamageTakenMeter::entries`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DamageTaken {
+    pub guid: Guid,
+    pub name: String,
+    // Including the damage shields absorbed.
+    pub total: i64,
+    pub absorbed: i64,
+    // Averaged over the active time, from the first to the last row processed.
+    pub per_second: f64,
+    // Most damage first.
+    pub abilities: Vec<AbilityDamageTaken>,
+}
+
+impl DamageTaken {
+    pub fn absorbed_percent(&self) -> f64 {
+        self.absorbed as f64 * 100.0 / self.total.max(1) as f64
+    }
+}
+
+/// Adds up the damage every player took in the rows passed to `process`,
+/// usually the rows of one pull. Damage absorbed by shields counts, as the
+/// player was hit all the same.
+#[derive(Default)]
+pub struct DamageTakenMeter {
+    // The name and the abilities that hit each player.
+    players: HashMap<Guid, (String, HashMap<i64, AbilityDamageTaken>)>,
+    start: Option<i64>,
+    end: i64,
+}
+
+impl DamageTakenMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn process(&mut self, timestamp: i64, row: &LogRow) {
+        self.start.get_or_insert(timestamp);
+        self.end = timestamp;
+
+        let Some(hit) = hit_of(row).filter(|hit| hit.dest_guid.is_player()) else {
+            return;
+        };
+        let (_, abilities) = self
+            .players
+            .entry(hit.dest_guid.clone())
+            .or_insert_with(|| {
+                (
+                    hit.dest_name.as_str().unwrap_or_default().to_string(),
+                    HashMap::new(),
+                )
+            });
+        let ability = abilities
+            .entry(hit.spell_id)
+            .or_insert_with(|| AbilityDamageTaken {
+                spell_id: hit.spell_id,
+                name: hit.spell_name.to_string(),
+                school: hit.school,
+                total: 0,
+                absorbed: 0,
+                hits: 0,
+            });
+        ability.total += hit.amount + hit.absorbed;
+        ability.absorbed += hit.absorbed;
+        ability.hits += hit.landed as u32;
+    }
+
+    /// Milliseconds from the first to the last row processed.
+    pub fn active_time(&self) -> i64 {
+        self.start.map_or(0, |start| self.end - start)
+    }
+
+    /// The damage taken by each player, most first.
+    pub fn entries(&self) -> Vec<DamageTaken> {
+        let seconds = (self.active_time() as f64 / 1000.0).max(1.0);
+        let mut entries: Vec<DamageTaken> = self
+            .players
+            .iter()
+            .map(|(guid, (name, abilities))| {
+                let mut abilities: Vec<AbilityDamageTaken> = abilities.values().cloned().collect();
+                abilities.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+                let total = abilities.iter().map(|ability| ability.total).sum();
+
+                DamageTaken {
+                    guid: guid.clone(),
+                    name: name.clone(),
+                    total,
+                    absorbed: abilities.iter().map(|ability| ability.absorbed).sum(),
+                    per_second: total as f64 / seconds,
+                    abilities,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{DamageMeter, HealingMeter};
+    use crate::parser::Parser;
+
+    #[test]
+    fn counts_absorbed_damage_against_the_attacker() {
+        // Rashok hits the druid for 40000, of which the priest's shield absorbs
+        // 10000, then a second hit and a melee swing are absorbed in full.
+        let log = "4/29 20:15:00.000  SPELL_DAMAGE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405316,\"Ancient Fury\",0x4,Player-1379-0A9FF58F,0000000000000000,1483954,1952835,9102,1200,5043,0,1,60,100,0,-5095.52,1142.47,2073,6.1556,70,30000,40000,-1,4,0,0,10000,nil,nil,nil\n\
+            4/29 20:15:00.000  SPELL_ABSORBED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405316,\"Ancient Fury\",0x4,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,17,\"Power Word: Shield\",0x2,10000,40000,nil\n\
+            4/29 20:15:02.000  SPELL_MISSED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405316,\"Ancient Fury\",0x4,ABSORB,nil,25000,25000,nil\n\
+            4/29 20:15:02.000  SPELL_ABSORBED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405316,\"Ancient Fury\",0x4,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,17,\"Power Word: Shield\",0x2,25000,25000,nil\n\
+            4/29 20:15:05.000  SWING_MISSED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,ABSORB,nil,5000,5000,nil\n\
+            4/29 20:15:05.000  SPELL_ABSORBED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,17,\"Power Word: Shield\",0x2,5000,5000,nil\n\
+            4/29 20:15:06.000  SPELL_ABSORBED,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,409057,\"Seismic Shield\",0x1,3000,3000,nil\n\
+            4/29 20:15:10.000  SPELL_MISSED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405316,\"Ancient Fury\",0x4,DODGE,nil\n";
+
+        let mut taken = DamageTakenMeter::new();
+        let mut damage = DamageMeter::new(true);
+        let mut healing = HealingMeter::new(true);
+        for event in Parser::rows(log.as_bytes()).flatten() {
+            taken.process(event.timestamp, &event.row);
+            damage.process(event.timestamp, &event.row);
+            healing.process(event.timestamp, &event.row);
+        }
+
+        let entries = taken.entries();
+        assert_eq!(entries.len(), 1);
+        let druid = &entries[0];
+        assert_eq!(druid.name, "Yerrog-Sanguino");
+        assert_eq!(druid.total, 70_000);
+        assert_eq!(druid.absorbed, 40_000);
+        assert_eq!(druid.per_second, 7_000.0);
+        let fury = &druid.abilities[0];
+        assert_eq!(fury.name, "Ancient Fury");
+        assert_eq!(fury.total, 65_000);
+        assert_eq!(fury.hits, 2);
+        let melee = &druid.abilities[1];
+        assert_eq!(melee.name, "Melee");
+        assert_eq!(melee.school, SpellSchool::PHYSICAL);
+        assert_eq!((melee.total, melee.hits), (5_000, 1));
+
+        // The druid's Brambles absorbed by Rashok's shield still counts as damage
+        // done, while the priest is credited with their shields.
+        let damage = damage.entries();
+        assert_eq!(damage[0].total, 3_000);
+        assert_eq!(damage[0].spells[0].hits, 0);
+        let healing = healing.entries();
+        assert_eq!(healing[0].name, "Priest-Sanguino");
+        assert_eq!(healing[0].healing, 40_000);
+    }
+}
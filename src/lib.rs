@@ -0,0 +1,5 @@
+#![allow(non_snake_case)]
+
+pub mod analysis;
+pub mod parser;
+pub mod settings;
@@ -1,11 +1,17 @@
 #![allow(non_snake_case)]
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
 
 // import the prelude to get access to the `rsx!` macro and the `Scope` and `Element` types
 use dioxus::prelude::*;
 use dioxus_router::prelude::*;
-
-mod parser;
+use wow_raid_analyzer::{analysis, parser, settings};
 
 fn main() {
     // launch the dioxus app in a webview
@@ -19,9 +25,21 @@ enum Route {
 
     #[route("/analyze/:log")]
     Analyze { log: String },
+
+    #[route("/analyze/:log/:pull")]
+    Pull { log: String, pull: usize },
+
+    #[route("/settings")]
+    Settings,
 }
 
+// The pulls of the logs opened so far, by file name, so that the page of a pull
+// does not have to parse its log again.
+#[derive(Default)]
+struct ParsedPulls(HashMap<String, Vec<parser::Encounter>>);
+
 fn App(cx: Scope) -> Element {
+    use_shared_state_provider(cx, ParsedPulls::default);
     render! {
         Router::<Route> {}
     }
@@ -29,22 +47,218 @@ fn App(cx: Scope) -> Element {
 
 // define a component that renders a div with the text "Hello, world!"
 fn Home(cx: Scope) -> Element {
-    let files = use_ref(cx, Logs::new);
+    let logs = use_ref(cx, Logs::new);
+    // Scanning a raid night's worth of logs takes a moment, so it is done off the UI thread.
+    let scanned = use_future(cx, (), |_| async move {
+        tokio::task::spawn_blocking(|| Logs::new().scan_log_files())
+            .await
+            .unwrap_or_else(|err| Err(err.into()))
+    });
+    // Caches of logs that were deleted or changed are not loaded again.
+    let pruned = use_future(cx, (), |_| async move {
+        tokio::task::spawn_blocking(|| {
+            parser::LogCache::in_user_cache_dir().map(|cache| cache.prune())
+        })
+        .await
+        .ok()
+        .flatten()
+    });
+    let prune_error = match pruned.value() {
+        Some(Some(Err(err))) => Some(format!("Could not prune the cache: {}", err)),
+        _ => None,
+    };
+    let (files, scan_error) = match scanned.value() {
+        Some(Ok(files)) => (files.as_slice(), None),
+        Some(Err(err)) => (&[][..], Some(format!("{:#}", err))),
+        None => (&[][..], None),
+    };
     render!(div {
         main {
             h1 { "Hello, world!" }
-            files.read().list_log_files().iter().map(|file| {
-                render!(div {
-                    i {
+            Link { to: Route::Settings, "Settings" }
+            prune_error.map(|err| rsx!(p { "{err}" }))
+            if logs.read().path.is_none() {
+                rsx!(p { "No World of Warcraft log directory was found." })
+            } else if scanned.value().is_none() {
+                rsx!(p { "Scanning the logs..." })
+            }
+            scan_error.map(|err| rsx!(p { "{err}" }))
+            table {
+                tr {
+                    th { "Log" }
+                    th { "Size" }
+                    th { "Modified" }
+                    th { "Zones" }
+                    th { "Pulls" }
+                    th { "Kills" }
+                    th { "Wipes" }
+                    th { "Duration" }
+                }
+                files.iter().map(|file| {
+                    let link = rsx!(td {
                         Link {
                             to: Route::Analyze {
-                                log: file.to_string()
+                                log: file.name.clone()
                             },
-                            "Analyze"
+                            "{file.name}"
+                        }
+                    });
+                    let scan = match &file.scan {
+                        Ok(scan) => scan,
+                        Err(err) => {
+                            return render!(tr {
+                                link
+                                td { colspan: "7", "Could not scan: {err}" }
+                            });
                         }
+                    };
+                    let size = format_size(scan.size);
+                    let modified = scan.modified.format("%a %Y-%m-%d %H:%M");
+                    let zones = scan.summary.zones.join(", ");
+                    let wipes = scan.summary.wipes();
+                    let duration = format_duration(scan.summary.duration);
+                    render!(tr {
+                        link
+                        td { "{size}" }
+                        td { "{modified}" }
+                        td { "{zones}" }
+                        td { "{scan.summary.encounters}" }
+                        td { "{scan.summary.kills}" }
+                        td { "{wipes}" }
+                        td { "{duration}" }
+                    })
+                })
+            }
+        }
+    })
+}
+
+fn format_size(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+}
+
+fn format_duration(ms: i64) -> String {
+    let minutes = ms / 60_000;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+// Edits the settings, which are only saved to the config file with the save button.
+fn Settings(cx: Scope) -> Element {
+    // A config file that cannot be read is replaced with the defaults on save.
+    let loaded = cx.use_hook(|| match settings::Settings::load() {
+        Ok(settings) => (settings, None),
+        Err(err) => (
+            settings::Settings::default(),
+            Some(format!(
+                "Could not load the settings, using the defaults: {:#}",
+                err
+            )),
+        ),
+    });
+    let settings = use_ref(cx, || loaded.0.clone());
+    let load_error = &loaded.1;
+    let detected = use_memo(cx, (), |_| settings::detect_log_dirs());
+    // One name per line, kept as typed until the settings are saved.
+    let raid_members = use_state(cx, || settings.read().raid_members.join("\n"));
+    let status = use_state(cx, String::new);
+
+    let current = settings.read().clone();
+    let log_dir = current
+        .log_dir
+        .as_ref()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    render!(div {
+        main {
+            h1 { "Settings" }
+            Link { to: Route::Home, "Back" }
+            load_error.as_ref().map(|err| rsx!(p { "{err}" }))
+            h2 { "Log directory" }
+            input {
+                value: "{log_dir}",
+                placeholder: "Detected",
+                oninput: move |evt| {
+                    settings.write().log_dir = (!evt.value.is_empty()).then(|| PathBuf::from(&evt.value));
+                }
+            }
+            detected.iter().map(|dir| {
+                let path = dir.path.clone();
+                let shown = path.display().to_string();
+                render!(div {
+                    "{dir.flavor}: {shown} "
+                    button {
+                        onclick: move |_| settings.write().log_dir = Some(path.clone()),
+                        "Use"
                     }
                 })
             })
+            h2 { "Flavor" }
+            p { "The client whose log directory is used when none is set." }
+            select {
+                onchange: move |evt| {
+                    let flavor = settings::Flavor::ALL
+                        .into_iter()
+                        .find(|flavor| flavor.folder() == evt.value);
+                    if let Some(flavor) = flavor {
+                        settings.write().flavor = flavor;
+                    }
+                },
+                settings::Flavor::ALL.into_iter().map(|flavor| {
+                    render!(option {
+                        value: flavor.folder(),
+                        selected: flavor == current.flavor,
+                        "{flavor}"
+                    })
+                })
+            }
+            h2 { "Death recaps" }
+            label {
+                "Seconds before a death "
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{current.death_recap_seconds}",
+                    oninput: move |evt| {
+                        if let Ok(seconds @ 1..) = evt.value.parse::<u32>() {
+                            settings.write().death_recap_seconds = seconds;
+                        }
+                    }
+                }
+            }
+            h2 { "Pets" }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: current.merge_pets,
+                    oninput: move |evt| settings.write().merge_pets = evt.value == "true"
+                }
+                " Count pets together with their owners"
+            }
+            h2 { "Our raid" }
+            p { "One player per line, e.g. Yerrog-Sanguino. Everyone in the group counts when empty." }
+            textarea {
+                rows: "10",
+                value: "{raid_members}",
+                oninput: move |evt| raid_members.set(evt.value.clone())
+            }
+            div {
+                button {
+                    onclick: move |_| {
+                        settings.write().raid_members = raid_members
+                            .lines()
+                            .map(str::trim)
+                            .filter(|name| !name.is_empty())
+                            .map(String::from)
+                            .collect();
+                        status.set(match settings.read().save() {
+                            Ok(()) => "Saved".to_string(),
+                            Err(err) => format!("Could not save the settings: {:#}", err),
+                        });
+                    },
+                    "Save"
+                }
+                " {status}"
+            }
         }
     })
 }
@@ -52,29 +266,699 @@ fn Home(cx: Scope) -> Element {
 #[inline_props]
 // define a component that renders a div with the text "Hello, world!"
 fn Analyze(cx: Scope, log: String) -> Element {
-    let logs = use_ref(cx, Logs::new);
+    let parsed_pulls = use_shared_state::<ParsedPulls>(cx).cloned();
+    // Parsing takes a while for a long raid night, so it is done off the UI thread.
+    let details = use_future(cx, (log,), |(log,)| async move {
+        let file = log.clone();
+        let details = tokio::task::spawn_blocking(move || Logs::new().read_log(file))
+            .await
+            .unwrap_or_else(|err| Err(err.into()));
+        if let (Some(parsed_pulls), Ok(details)) = (parsed_pulls, &details) {
+            parsed_pulls
+                .write()
+                .0
+                .insert(log, details.encounters.clone());
+        }
+        details
+    });
+    let following = use_state(cx, || false);
+    let parsed = details.value().filter(|_| !*following.get());
     render!(div {
         main {
-            h1 { "Hello, world!" }
-            logs.read().read_log(log.to_string())
+            h1 { "{log}" }
+            Link { to: Route::Home, "Back" }
+            button {
+                onclick: move |_| following.set(!following.get()),
+                if *following.get() { "Stop following" } else { "Follow live" }
+            }
+            if *following.get() {
+                render!(LiveView { log: log.to_string() })
+            } else if parsed.is_none() {
+                render!(p { "Parsing the log..." })
+            }
+            parsed.into_iter().map(|details| match details {
+                Ok(LogDetails { encounters, cache_error }) => render!(
+                    cache_error.as_ref().map(|err| rsx!(p { "Could not cache the log: {err}" }))
+                    analysis::boss_progress(encounters).into_iter().map(|boss| {
+                        let pulls: Vec<_> = boss
+                            .pulls
+                            .iter()
+                            .map(|&index| (index, encounters[index].clone()))
+                            .collect();
+                        render!(BossPulls { log: log.to_string(), pulls: pulls })
+                    })
+                ),
+                Err(err) => {
+                    let message = format!("{:#}", err);
+                    render!(p { "{message}" })
+                }
+            })
         }
     })
 }
 
+#[inline_props]
+// The pulls of one boss on one difficulty, each linking to its details.
+fn BossPulls(cx: Scope, log: String, pulls: Vec<(usize, parser::Encounter)>) -> Element {
+    let first = &pulls[0].1;
+    let difficulty = analysis::difficulty_name(first.difficulty_id);
+    let count = pulls.len();
+    let kills = pulls.iter().filter(|(_, pull)| pull.success).count();
+    render!(section {
+        h2 { "{first.name} ({difficulty})" }
+        p { "{count} pulls, {kills} kills" }
+        ProgressChart { pulls: pulls.iter().map(|(_, pull)| pull.clone()).collect() }
+        table {
+            tr {
+                th { "Pull" }
+                th { "Duration" }
+                th { "Outcome" }
+                th { "Boss health" }
+            }
+            pulls.iter().enumerate().map(|(number, (index, pull))| {
+                let number = number + 1;
+                let duration = format_fight_time(pull.fight_time);
+                let health = boss_health_of(pull);
+                render!(tr {
+                    td {
+                        Link {
+                            to: Route::Pull {
+                                log: log.clone(),
+                                pull: *index
+                            },
+                            "#{number}"
+                        }
+                    }
+                    td { "{duration}" }
+                    td { if pull.success { "Kill" } else { "Wipe" } }
+                    td { "{health}" }
+                })
+            })
+        }
+    })
+}
+
+const CHART_WIDTH: usize = 600;
+const CHART_HEIGHT: usize = 100;
+
+#[inline_props]
+// The boss health each pull ended at, so that progress over the night shows as a
+// line going down: full health is at the top, kills at 0% at the bottom. Pulls
+// without advanced logging are left out.
+fn ProgressChart(cx: Scope, pulls: Vec<parser::Encounter>) -> Element {
+    // Fractional, so that a long night still spreads its pulls over the chart.
+    let step = CHART_WIDTH as f64 / pulls.len().max(1) as f64;
+    let points: Vec<(f64, f64, bool)> = pulls
+        .iter()
+        .enumerate()
+        .filter_map(|(index, pull)| {
+            let health = if pull.success {
+                0.0
+            } else {
+                pull.boss_health.as_ref()?.percent()
+            };
+            // SVG coordinates grow downwards.
+            let y = (100.0 - health) * CHART_HEIGHT as f64 / 100.0;
+            Some(((index as f64 + 0.5) * step, y, pull.success))
+        })
+        .collect();
+    let line = points
+        .iter()
+        .map(|(x, y, _)| format!("{:.1},{:.1}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ");
+    render!(svg {
+        width: "{CHART_WIDTH}",
+        height: "{CHART_HEIGHT}",
+        polyline { points: "{line}", fill: "none", stroke: "gray" }
+        points.iter().map(|(x, y, success)| {
+            let color = if *success { "green" } else { "red" };
+            let (x, y) = (format!("{:.1}", x), format!("{:.1}", y));
+            render!(circle { cx: "{x}", cy: "{y}", r: "4", fill: "{color}" })
+        })
+    })
+}
+
+#[inline_props]
+// A single pull, with the deaths of the raid.
+fn Pull(cx: Scope, log: String, pull: usize) -> Element {
+    // Known unless the page was opened without going through the page of the log.
+    let known = use_shared_state::<ParsedPulls>(cx)
+        .and_then(|parsed| parsed.read().0.get(log)?.get(*pull).cloned());
+    let details = use_future(cx, (log, pull), |(log, pull)| async move {
+        tokio::task::spawn_blocking(move || {
+            let logs = Logs::new();
+            let encounter = match known {
+                Some(encounter) => encounter,
+                None => logs
+                    .read_log(log.clone())?
+                    .encounters
+                    .into_iter()
+                    .nth(pull)
+                    .with_context(|| format!("{} has no pull {}", log, pull + 1))?,
+            };
+            logs.read_pull(log, encounter)
+        })
+        .await
+        .unwrap_or_else(|err| Err(err.into()))
+    });
+    let Some(details) = details.value() else {
+        return render!(p { "Parsing the log..." });
+    };
+    let PullDetails {
+        encounter,
+        deaths,
+        damage,
+        healing,
+        damage_taken,
+    } = match details {
+        Ok(details) => details,
+        Err(err) => {
+            let message = format!("This pull could not be read: {:#}", err);
+            return render!(p { "{message}" });
+        }
+    };
+
+    let difficulty = analysis::difficulty_name(encounter.difficulty_id);
+    let duration = format_fight_time(encounter.fight_time);
+    let health = boss_health_of(encounter);
+    render!(div {
+        main {
+            h1 { "{encounter.name} ({difficulty})" }
+            Link { to: Route::Analyze { log: log.clone() }, "Back" }
+            p {
+                if encounter.success { "Kill" } else { "Wipe" }
+                " after {duration}, boss at {health}"
+            }
+            h2 { "Damage done" }
+            DamageTable { entries: damage.clone() }
+            h2 { "Healing done" }
+            HealingTable { entries: healing.clone() }
+            h2 { "Damage taken" }
+            DamageTakenTable { entries: damage_taken.clone() }
+            h2 { "Deaths" }
+            deaths.iter().map(|death| {
+                let killing_blow = death.events.last();
+                let spell = killing_blow.map_or("?", |entry| &entry.spell_name);
+                let source = killing_blow.map_or("?", |entry| &entry.source_name);
+                let time = format_fight_time(death.timestamp - encounter.start_time);
+                render!(div { "{time} {death.unit_name} died to {spell} ({source})" })
+            })
+        }
+    })
+}
+
+// The column a table is sorted by, and whether the most comes first.
+#[derive(PartialEq, Clone, Copy)]
+struct SortBy {
+    column: usize,
+    descending: bool,
+}
+
+impl SortBy {
+    fn new(column: usize) -> Self {
+        Self {
+            column,
+            descending: true,
+        }
+    }
+
+    // Clicking the column sorted by again reverses the order.
+    fn click(self, column: usize) -> Self {
+        Self {
+            column,
+            descending: column != self.column || !self.descending,
+        }
+    }
+
+    fn apply<T>(&self, rows: &mut [T], compare: impl Fn(usize, &T, &T) -> Ordering) {
+        rows.sort_by(|a, b| {
+            let order = compare(self.column, a, b);
+            if self.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    fn arrow(&self, column: usize) -> &'static str {
+        match (column == self.column, self.descending) {
+            (false, _) => "",
+            (true, true) => " \u{25BC}",
+            (true, false) => " \u{25B2}",
+        }
+    }
+}
+
+// Sorts a copy of `rows` by the column picked in the `SortHeader` of the table,
+// the second column first.
+fn use_sorted<'a, T: Clone>(
+    cx: &'a ScopeState,
+    rows: &[T],
+    compare: impl Fn(usize, &T, &T) -> Ordering,
+) -> (&'a UseState<SortBy>, Vec<T>) {
+    let sort = use_state(cx, || SortBy::new(1));
+    let mut rows = rows.to_vec();
+    sort.get().apply(&mut rows, compare);
+    (sort, rows)
+}
+
+#[inline_props]
+// The header row of a sortable table. Clicking a column sorts the table by it.
+fn SortHeader<'a>(
+    cx: Scope<'a>,
+    sort: &'a UseState<SortBy>,
+    labels: &'static [&'static str],
+) -> Element<'a> {
+    render!(tr {
+        labels.iter().enumerate().map(|(column, label)| {
+            let arrow = sort.get().arrow(column);
+            rsx!(th {
+                key: "{column}",
+                onclick: move |_| sort.set(sort.get().click(column)),
+                "{label}{arrow}"
+            })
+        })
+    })
+}
+
+// Opens the details of a row, or closes them when they were open.
+fn toggle(selected: &UseState<Option<parser::Guid>>, guid: &parser::Guid) {
+    let open = selected.get().as_ref() != Some(guid);
+    selected.set(open.then(|| guid.clone()));
+}
+
+#[inline_props]
+// The damage done by each player. Clicking a player shows the spells it was done with.
+fn DamageTable(cx: Scope, entries: Vec<analysis::DamageDone>) -> Element {
+    let selected = use_state(cx, || None::<parser::Guid>);
+    let (sort, entries) = use_sorted(cx, entries, |column, a, b| match column {
+        0 => a.name.cmp(&b.name),
+        1 => a.total.cmp(&b.total),
+        _ => a.per_second.total_cmp(&b.per_second),
+    });
+    render!(div {
+        table {
+            SortHeader { sort: sort, labels: &["Player", "Damage", "DPS"] }
+            entries.iter().map(|entry| {
+                let guid = entry.guid.clone();
+                let name = unit_name(&entry.name, &entry.owner);
+                let total = format_amount(entry.total);
+                let per_second = format_amount(entry.per_second as i64);
+                render!(tr {
+                    onclick: move |_| toggle(selected, &guid),
+                    td { "{name}" }
+                    td { "{total}" }
+                    td { "{per_second}" }
+                })
+            })
+        }
+        entries.iter().filter(|entry| Some(&entry.guid) == selected.get().as_ref()).map(|entry| {
+            let name = unit_name(&entry.name, &entry.owner);
+            let pets = entry.pets.iter().map(|pet| {
+                let name = format!("{} ({})", pet.name, format_amount(pet.total));
+                rsx!(SpellDamageTable { name: name, spells: pet.spells.clone() })
+            });
+            render!(
+                SpellDamageTable { name: name, spells: entry.spells.clone() }
+                pets
+            )
+        })
+    })
+}
+
+#[inline_props]
+fn SpellDamageTable(cx: Scope, name: String, spells: Vec<analysis::SpellDamageDone>) -> Element {
+    let (sort, spells) = use_sorted(cx, spells, |column, a, b| match column {
+        0 => a.name.cmp(&b.name),
+        1 => a.total.cmp(&b.total),
+        2 => a.hits.cmp(&b.hits),
+        3 => a.crit_percent().total_cmp(&b.crit_percent()),
+        4 => a.average_hit().cmp(&b.average_hit()),
+        _ => a.max_hit.cmp(&b.max_hit),
+    });
+    render!(div {
+        h3 { "{name}" }
+        table {
+            SortHeader {
+                sort: sort,
+                labels: &["Spell", "Damage", "Hits", "Crit %", "Average", "Max"]
+            }
+            spells.iter().map(|spell| {
+                let color = spell.school.color();
+                let total = format_amount(spell.total);
+                let crits = format!("{:.1}%", spell.crit_percent());
+                let average = format_amount(spell.average_hit());
+                let max_hit = format_amount(spell.max_hit);
+                render!(tr {
+                    td { style: "color: {color}", "{spell.name}" }
+                    td { "{total}" }
+                    td { "{spell.hits}" }
+                    td { "{crits}" }
+                    td { "{average}" }
+                    td { "{max_hit}" }
+                })
+            })
+        }
+    })
+}
+
+#[inline_props]
+// The healing done by each player, shields included. Clicking a player shows the
+// spells it was done with.
+fn HealingTable(cx: Scope, entries: Vec<analysis::HealingDone>) -> Element {
+    let selected = use_state(cx, || None::<parser::Guid>);
+    let (sort, entries) = use_sorted(cx, entries, |column, a, b| match column {
+        0 => a.name.cmp(&b.name),
+        1 => a.healing.cmp(&b.healing),
+        2 => a.per_second.total_cmp(&b.per_second),
+        _ => a.overheal_percent().total_cmp(&b.overheal_percent()),
+    });
+    render!(div {
+        table {
+            SortHeader { sort: sort, labels: &["Player", "Healing", "HPS", "Overheal"] }
+            entries.iter().map(|entry| {
+                let guid = entry.guid.clone();
+                let name = unit_name(&entry.name, &entry.owner);
+                let healing = format_amount(entry.healing);
+                let per_second = format_amount(entry.per_second as i64);
+                let overheal = format!("{:.1}%", entry.overheal_percent());
+                render!(tr {
+                    onclick: move |_| toggle(selected, &guid),
+                    td { "{name}" }
+                    td { "{healing}" }
+                    td { "{per_second}" }
+                    td { "{overheal}" }
+                })
+            })
+        }
+        entries.iter().filter(|entry| Some(&entry.guid) == selected.get().as_ref()).map(|entry| {
+            let name = unit_name(&entry.name, &entry.owner);
+            let pets = entry.pets.iter().map(|pet| {
+                let name = format!("{} ({})", pet.name, format_amount(pet.healing));
+                rsx!(SpellHealingTable { name: name, spells: pet.spells.clone() })
+            });
+            render!(
+                SpellHealingTable { name: name, spells: entry.spells.clone() }
+                pets
+            )
+        })
+    })
+}
+
+#[inline_props]
+fn SpellHealingTable(cx: Scope, name: String, spells: Vec<analysis::SpellHealingDone>) -> Element {
+    let (sort, spells) = use_sorted(cx, spells, |column, a, b| match column {
+        0 => a.name.cmp(&b.name),
+        1 => a.healing.cmp(&b.healing),
+        2 => a.overheal_percent().total_cmp(&b.overheal_percent()),
+        3 => a.hits.cmp(&b.hits),
+        4 => a.crit_percent().total_cmp(&b.crit_percent()),
+        _ => a.max_hit.cmp(&b.max_hit),
+    });
+    render!(div {
+        h3 { "{name}" }
+        table {
+            SortHeader {
+                sort: sort,
+                labels: &["Spell", "Healing", "Overheal", "Hits", "Crit %", "Max"]
+            }
+            spells.iter().map(|spell| {
+                let color = spell.school.color();
+                let healing = format_amount(spell.healing);
+                let overheal = format!("{:.1}%", spell.overheal_percent());
+                let crits = format!("{:.1}%", spell.crit_percent());
+                let max_hit = format_amount(spell.max_hit);
+                render!(tr {
+                    td { style: "color: {color}", "{spell.name}" }
+                    td { "{healing}" }
+                    td { "{overheal}" }
+                    td { "{spell.hits}" }
+                    td { "{crits}" }
+                    td { "{max_hit}" }
+                })
+            })
+        }
+    })
+}
+
+#[inline_props]
+// The damage taken by each player, shields included. Clicking a player shows the
+// abilities that hit them.
+fn DamageTakenTable(cx: Scope, entries: Vec<analysis::DamageTaken>) -> Element {
+    let selected = use_state(cx, || None::<parser::Guid>);
+    let (sort, entries) = use_sorted(cx, entries, |column, a, b| match column {
+        0 => a.name.cmp(&b.name),
+        1 => a.total.cmp(&b.total),
+        2 => a.per_second.total_cmp(&b.per_second),
+        _ => a.absorbed_percent().total_cmp(&b.absorbed_percent()),
+    });
+    render!(div {
+        table {
+            SortHeader { sort: sort, labels: &["Player", "Damage taken", "DTPS", "Absorbed"] }
+            entries.iter().map(|entry| {
+                let guid = entry.guid.clone();
+                let total = format_amount(entry.total);
+                let per_second = format_amount(entry.per_second as i64);
+                let absorbed = format!("{:.1}%", entry.absorbed_percent());
+                render!(tr {
+                    onclick: move |_| toggle(selected, &guid),
+                    td { "{entry.name}" }
+                    td { "{total}" }
+                    td { "{per_second}" }
+                    td { "{absorbed}" }
+                })
+            })
+        }
+        entries.iter().filter(|entry| Some(&entry.guid) == selected.get().as_ref()).map(|entry| {
+            render!(AbilityDamageTakenTable {
+                name: entry.name.clone(),
+                abilities: entry.abilities.clone()
+            })
+        })
+    })
+}
+
+#[inline_props]
+fn AbilityDamageTakenTable(
+    cx: Scope,
+    name: String,
+    abilities: Vec<analysis::AbilityDamageTaken>,
+) -> Element {
+    let (sort, abilities) = use_sorted(cx, abilities, |column, a, b| match column {
+        0 => a.name.cmp(&b.name),
+        1 => a.total.cmp(&b.total),
+        2 => a.absorbed.cmp(&b.absorbed),
+        _ => a.hits.cmp(&b.hits),
+    });
+    render!(div {
+        h3 { "{name}" }
+        table {
+            SortHeader { sort: sort, labels: &["Ability", "Damage", "Absorbed", "Hits"] }
+            abilities.iter().map(|ability| {
+                let color = ability.school.color();
+                let total = format_amount(ability.total);
+                let absorbed = format_amount(ability.absorbed);
+                render!(tr {
+                    td { style: "color: {color}", "{ability.name}" }
+                    td { "{total}" }
+                    td { "{absorbed}" }
+                    td { "{ability.hits}" }
+                })
+            })
+        }
+    })
+}
+
+// A pet listed on its own is named after its owner as well.
+fn unit_name(name: &str, owner: &Option<String>) -> String {
+    match owner {
+        Some(owner) => format!("{} ({})", name, owner),
+        None => name.to_string(),
+    }
+}
+
+// `1.23M`, `45.6k` or `789`, the way meters in the game show amounts.
+fn format_amount(amount: i64) -> String {
+    match amount.abs() {
+        1_000_000.. => format!("{:.2}M", amount as f64 / 1_000_000.0),
+        1_000.. => format!("{:.1}k", amount as f64 / 1_000.0),
+        _ => amount.to_string(),
+    }
+}
+
+fn format_fight_time(ms: i64) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn boss_health_of(pull: &parser::Encounter) -> String {
+    match (pull.success, &pull.boss_health) {
+        (true, _) => "0%".to_string(),
+        (false, Some(health)) => format!("{:.1}%", health.percent()),
+        (false, None) => "?".to_string(),
+    }
+}
+
+#[inline_props]
+fn EncounterSummary(cx: Scope, encounter: parser::Encounter) -> Element {
+    let duration = format_fight_time(encounter.fight_time);
+    render!(div {
+        "{encounter.name} ({duration}) "
+        if encounter.success { "Kill" } else { "Wipe" }
+    })
+}
+
+// Rows are read in batches, so that the window stays responsive while a long log
+// is caught up with.
+const LIVE_BATCH_SIZE: usize = 10_000;
+const LIVE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[inline_props]
+// Follows a log while it is written, with a damage meter and the deaths of the current pull.
+fn LiveView(cx: Scope, log: String) -> Element {
+    let live = use_ref(cx, || {
+        let settings = Logs::new().settings;
+        analysis::LiveLog::new(
+            settings.death_recap_window(),
+            settings.raid_members.into_iter().collect(),
+            settings.merge_pets,
+        )
+    });
+    let open_error = use_state(cx, || None::<String>);
+    use_future(cx, (log,), |(log,)| {
+        to_owned![live, open_error];
+        async move {
+            // Reading and parsing the log blocks, so it is done off the UI thread.
+            let path = Logs::new().path_of(&log);
+            let opened = tokio::task::spawn_blocking(move || parser::LogTail::open(path)).await;
+            let mut tail = match opened.unwrap_or_else(|err| Err(err.into())) {
+                Ok(tail) => tail,
+                Err(err) => {
+                    open_error.set(Some(format!("Could not follow {}: {}", log, err)));
+                    return;
+                }
+            };
+
+            loop {
+                let batch = tokio::task::spawn_blocking(move || {
+                    let rows: Vec<_> = tail.by_ref().take(LIVE_BATCH_SIZE).collect();
+                    let restarted = tail.restarted();
+                    (tail, rows, restarted)
+                });
+                let Ok((returned, rows, restarted)) = batch.await else {
+                    return;
+                };
+                tail = returned;
+                let caught_up = rows.len() < LIVE_BATCH_SIZE && !restarted;
+                if !rows.is_empty() || restarted {
+                    let mut live = live.write();
+                    for row in rows {
+                        live.push(row);
+                    }
+                    // The rows read so far were those of the old log.
+                    if restarted {
+                        live.clear();
+                    }
+                }
+
+                if caught_up {
+                    tokio::time::sleep(LIVE_POLL_INTERVAL).await;
+                } else {
+                    tokio::task::yield_now().await;
+                }
+            }
+        }
+    });
+
+    if let Some(err) = open_error.get() {
+        return render!(p { "{err}" });
+    }
+    let live = live.read();
+    let errors = live.errors();
+    render!(div {
+        if errors > 0 {
+            rsx!(p { "{errors} lines could not be read and were skipped." })
+        }
+        h2 { "Pulls" }
+        live.encounters().map(|encounter| {
+            render!(EncounterSummary { encounter: encounter.clone() })
+        })
+        h2 { "Damage done" }
+        live.meter().into_iter().map(|entry| {
+            let amount = format_amount(entry.amount);
+            let per_second = format_amount(entry.per_second);
+            render!(div { "{entry.name}: {amount} ({per_second}/s)" })
+        })
+        h2 { "Deaths" }
+        live.deaths().map(|death| {
+            let killing_blow = death.events.last();
+            let spell = killing_blow.map_or("?", |entry| &entry.spell_name);
+            let source = killing_blow.map_or("?", |entry| &entry.source_name);
+            render!(div { "{death.unit_name} died to {spell} ({source})" })
+        })
+    })
+}
+
+// What the page of a log shows.
+#[derive(PartialEq, Clone)]
+struct LogDetails {
+    encounters: Vec<parser::Encounter>,
+    // Why the log could not be cached, if it could not.
+    cache_error: Option<String>,
+}
+
+// What the page of a pull shows.
+#[derive(PartialEq, Clone)]
+struct PullDetails {
+    encounter: parser::Encounter,
+    // Players of the raid that died, oldest first.
+    deaths: Vec<analysis::DeathRecap>,
+    damage: Vec<analysis::DamageDone>,
+    healing: Vec<analysis::HealingDone>,
+    damage_taken: Vec<analysis::DamageTaken>,
+}
+
+// A log in the list on the home page.
+#[derive(PartialEq, Clone)]
+struct LogFileInfo {
+    name: String,
+    // Why the log could not be scanned, when it could not. It is still listed.
+    scan: Result<LogScan, String>,
+}
+
+#[derive(PartialEq, Clone)]
+struct LogScan {
+    size: u64,
+    // On the local clock, like the logs themselves.
+    modified: DateTime<Local>,
+    summary: parser::LogSummary,
+}
+
 struct Logs {
-    path: String,
+    settings: settings::Settings,
+    // `None` when no log directory was configured and none was detected.
+    path: Option<PathBuf>,
 }
 
 impl Logs {
     fn new() -> Self {
+        // The settings page tells why the settings could not be loaded.
+        let settings = settings::Settings::load().unwrap_or_default();
         Self {
-            path: "C:\\Program Files (x86)\\World of Warcraft\\_retail_\\Logs".to_string(),
+            path: settings.log_dir(),
+            settings,
         }
     }
 
-    fn list_log_files(&self) -> Vec<String> {
+    fn list_log_files(&self) -> anyhow::Result<Vec<String>> {
         let mut files = Vec::new();
-        let entries = std::fs::read_dir(&self.path).expect("Could not read directory");
+        let Some(path) = &self.path else {
+            return Ok(files);
+        };
+        let entries = std::fs::read_dir(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
 
         for entry in entries {
             if let Ok(entry) = entry {
@@ -90,13 +974,99 @@ impl Logs {
             }
         }
 
-        files
+        Ok(files)
     }
 
-    fn read_log(&self, file: String) {
-        let path = format!("{}\\{}", self.path, file);
-        let parser = parser::Parser::new();
-        parser.parse_file(path);
+    /// Every log with its size, modification time and summary, newest first.
+    /// Logs that could not be scanned come last.
+    fn scan_log_files(&self) -> anyhow::Result<Vec<LogFileInfo>> {
+        let mut files: Vec<LogFileInfo> = self
+            .list_log_files()?
+            .into_iter()
+            .map(|file| LogFileInfo {
+                scan: self.scan_log_file(&file).map_err(|err| err.to_string()),
+                name: file,
+            })
+            .collect();
+        files.sort_by_key(|file| {
+            std::cmp::Reverse(file.scan.as_ref().ok().map(|scan| scan.modified))
+        });
+
+        Ok(files)
+    }
+
+    fn scan_log_file(&self, file: &str) -> std::io::Result<LogScan> {
+        let path = self.path_of(file);
+        let metadata = std::fs::metadata(&path)?;
+        Ok(LogScan {
+            size: metadata.len(),
+            modified: metadata.modified()?.into(),
+            summary: parser::LogFile::open(&path)?.summary(),
+        })
+    }
+
+    fn path_of(&self, file: &str) -> PathBuf {
+        self.path.clone().unwrap_or_default().join(file)
+    }
+
+    fn read_log(&self, file: String) -> anyhow::Result<LogDetails> {
+        let mut parser = parser::Parser::new();
+        let encounters = parser
+            .parse_file(self.path_of(&file))
+            .map_err(|err| anyhow::anyhow!("{} {}", file, err.kind))?;
+        Ok(LogDetails {
+            encounters,
+            cache_error: parser.cache_error().map(ToString::to_string),
+        })
+    }
+
+    // A pull of a log, analyzed for the players of the raid.
+    fn read_pull(&self, file: String, encounter: parser::Encounter) -> anyhow::Result<PullDetails> {
+        let raid = &self.settings.raid_members;
+        let in_raid = |name: &String| raid.is_empty() || raid.contains(name);
+        // Pets are often summoned before the pull, so their owners are taken from
+        // the rows before it too.
+        let path = self.path_of(&file);
+        let pet_owners = analysis::PetOwners::from_events;
+        parser::Parser::analyze_encounter(path, &encounter, pet_owners, |owners, events| {
+            let merge_pets = self.settings.merge_pets;
+            let mut deaths = analysis::DeathRecaps::new(self.settings.death_recap_window());
+            let mut damage = analysis::DamageMeter::with_owners(merge_pets, owners.clone());
+            let mut healing = analysis::HealingMeter::with_owners(merge_pets, owners);
+            let mut damage_taken = analysis::DamageTakenMeter::new();
+            for event in events {
+                deaths.process(event.timestamp, &event.row);
+                damage.process(event.timestamp, &event.row);
+                healing.process(event.timestamp, &event.row);
+                damage_taken.process(event.timestamp, &event.row);
+            }
+
+            PullDetails {
+                encounter: encounter.clone(),
+                deaths: deaths
+                    .deaths()
+                    .iter()
+                    .filter(|death| death.unit_guid.is_player() && in_raid(&death.unit_name))
+                    .cloned()
+                    .collect(),
+                damage: damage
+                    .entries()
+                    .into_iter()
+                    .filter(|entry| in_raid(entry.owner.as_ref().unwrap_or(&entry.name)))
+                    .collect(),
+                healing: healing
+                    .entries()
+                    .into_iter()
+                    .filter(|entry| in_raid(entry.owner.as_ref().unwrap_or(&entry.name)))
+                    .collect(),
+                damage_taken: damage_taken
+                    .entries()
+                    .into_iter()
+                    .filter(|entry| in_raid(&entry.name))
+                    .collect(),
+            }
+        })
+        .map_err(|err| anyhow::anyhow!("{} {}", file, err.kind))
     }
 }
 
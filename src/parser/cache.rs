@@ -0,0 +1,427 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use memmap2::Mmap;
+
+use super::columns::*;
+use super::error::ParseError;
+use super::guid::Guid;
+use super::mapped::ParsedLog;
+use super::rows::Event;
+
+const MAGIC: &[u8; 4] = b"WLAC";
+// Bumped whenever the encoding changes, which invalidates every existing cache.
+const FORMAT_VERSION: u32 = 6;
+
+/// Identifies the contents of a log. A log that was appended to or replaced
+/// since it was cached no longer matches its key.
+#[derive(Debug, PartialEq, Clone)]
+struct CacheKey {
+    path: String,
+    size: u64,
+    // Time since the Unix epoch.
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+impl CacheKey {
+    fn of(log: &Path) -> io::Result<Self> {
+        let path = fs::canonicalize(log)?;
+        let metadata = fs::metadata(&path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+            size: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        write_str(out, &self.path);
+        write_varint(out, self.size);
+        write_varint(out, self.modified_secs);
+        write_varint(out, self.modified_nanos.into());
+    }
+
+    fn read(input: &mut &[u8]) -> Option<Self> {
+        Some(Self {
+            path: read_str(input)?,
+            size: read_varint(input)?,
+            modified_secs: read_varint(input)?,
+            modified_nanos: read_varint(input)?.try_into().ok()?,
+        })
+    }
+}
+
+/// A directory of parsed logs, so that a log only has to be parsed again once
+/// it changed.
+///
+/// Every log is cached in a file of its own. Strings and GUIDs are stored once
+/// in a table and referred to by index, and the fields of all rows are stored
+/// in columns by kind, e.g. all integers next to each other.
+pub struct LogCache {
+    dir: PathBuf,
+}
+
+impl LogCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache in the user's cache directory, e.g. `%LOCALAPPDATA%` on Windows.
+    pub fn in_user_cache_dir() -> Option<Self> {
+        dirs::cache_dir().map(|dir| Self::new(dir.join("wow-log-analyzer")))
+    }
+
+    // Named after a hash of the path of the log, the key itself is checked on load.
+    // The hash has to stay the same across builds, so it is FNV-1a rather than
+    // the standard library's hasher.
+    fn path_of(&self, key: &CacheKey) -> PathBuf {
+        let hash = key
+            .path
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        self.dir.join(format!("{hash:016x}.bin"))
+    }
+
+    /// Removes the caches of logs that were deleted or changed since, and those
+    /// written by other versions. Returns the number of caches removed.
+    pub fn prune(&self) -> io::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "bin") {
+                continue;
+            }
+            let current = CachedLog::open(&path).is_ok_and(|cached| {
+                CacheKey::of(Path::new(&cached.key.path))
+                    .is_ok_and(|key| key == cached.key && self.path_of(&key) == path)
+            });
+            if !current {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// The cached rows of `log`, or `None` if it was not cached or changed since.
+    pub fn load(&self, log: impl AsRef<Path>) -> Option<CachedLog> {
+        let key = CacheKey::of(log.as_ref()).ok()?;
+        let cached = CachedLog::open(&self.path_of(&key)).ok()?;
+
+        (cached.key == key).then_some(cached)
+    }
+
+    /// Starts caching the rows of `log`. The key is taken right away, so a log
+    /// that grows while it is read is parsed again the next time.
+    pub fn writer(&self, log: impl AsRef<Path>) -> io::Result<CacheWriter> {
+        let key = CacheKey::of(log.as_ref())?;
+
+        Ok(CacheWriter {
+            path: self.path_of(&key),
+            key,
+            encoder: Encoder::default(),
+            rows: 0,
+            line: 0,
+            offset: 0,
+            timestamp: 0,
+        })
+    }
+}
+
+/// Collects the rows of a log in the order they were read, created by
+/// `LogCache::writer`.
+pub struct CacheWriter {
+    path: PathBuf,
+    key: CacheKey,
+    encoder: Encoder,
+    rows: u64,
+    // Line numbers, offsets and timestamps are stored relative to the previous row.
+    line: usize,
+    offset: usize,
+    timestamp: i64,
+}
+
+impl CacheWriter {
+    pub fn push(&mut self, row: &Result<Event, ParseError>) {
+        let out = &mut self.encoder;
+        match row {
+            Ok(event) => {
+                out.tag(0);
+                out.int(event.line as i64 - self.line as i64);
+                out.int(event.offset as i64 - self.offset as i64);
+                out.int(event.timestamp - self.timestamp);
+                event.date_time.encode(out);
+                event.row.encode(out);
+                self.line = event.line;
+                self.offset = event.offset;
+                self.timestamp = event.timestamp;
+            }
+            Err(err) => {
+                out.tag(1);
+                err.encode(out);
+            }
+        }
+        self.rows += 1;
+    }
+
+    /// Writes the cache, replacing the cache of an older version of the log.
+    pub fn finish(self) -> io::Result<()> {
+        let mut meta = Vec::new();
+        self.key.write(&mut meta);
+        write_varint(&mut meta, self.rows);
+
+        let mut strings = Vec::new();
+        write_varint(&mut strings, self.encoder.strings.len() as u64);
+        for string in &self.encoder.strings {
+            write_str(&mut strings, string);
+        }
+
+        let mut guids = Vec::new();
+        write_varint(&mut guids, self.encoder.guids.len() as u64);
+        for guid in &self.encoder.guids {
+            write_guid(&mut guids, guid);
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Written under another name first, so that a half written cache is never read.
+        let partial = self.path.with_extension("partial");
+        let mut file = BufWriter::new(File::create(&partial)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let columns = self.encoder.columns.each();
+        for section in [&meta, &strings, &guids].into_iter().chain(columns) {
+            file.write_all(&(section.len() as u64).to_le_bytes())?;
+            file.write_all(section)?;
+        }
+        file.into_inner()?.sync_all()?;
+
+        fs::rename(&partial, &self.path)
+    }
+}
+
+/// A cached log mapped into memory, created by `LogCache::load`.
+pub struct CachedLog {
+    map: Mmap,
+    key: CacheKey,
+    rows: u64,
+    strings: Vec<String>,
+    guids: Vec<Guid>,
+    // The byte range of every column in the map.
+    columns: Columns<Range<usize>>,
+}
+
+impl CachedLog {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only read. Caches are never written in place, a
+        // new cache is renamed over the old one, which leaves the mapped file
+        // untouched.
+        let map = unsafe { Mmap::map(&file)? };
+
+        Self::read(map).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid cache"))
+    }
+
+    fn read(map: Mmap) -> Option<Self> {
+        let header = map.get(..8)?;
+        if &header[..4] != MAGIC || header[4..] != FORMAT_VERSION.to_le_bytes() {
+            return None;
+        }
+
+        let mut sections = Vec::new();
+        let mut offset = header.len();
+        while offset < map.len() {
+            let len = u64::from_le_bytes(map.get(offset..offset + 8)?.try_into().ok()?);
+            let start = offset + 8;
+            let end = start.checked_add(usize::try_from(len).ok()?)?;
+            if end > map.len() {
+                return None;
+            }
+            sections.push(start..end);
+            offset = end;
+        }
+        let [meta, strings, guids, columns @ ..]: [Range<usize>; 9] = sections.try_into().ok()?;
+
+        let mut input = &map[meta];
+        let key = CacheKey::read(&mut input)?;
+        let rows = read_varint(&mut input)?;
+
+        let mut input = &map[strings];
+        let strings = (0..read_varint(&mut input)?)
+            .map(|_| read_str(&mut input))
+            .collect::<Option<_>>()?;
+
+        let mut input = &map[guids];
+        let guids = (0..read_varint(&mut input)?)
+            .map(|_| read_guid(&mut input))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            map,
+            key,
+            rows,
+            strings,
+            guids,
+            columns: Columns::from_each(columns),
+        })
+    }
+
+    /// Streams the cached rows, in the order they were read from the log.
+    pub fn rows(&self) -> CachedRows<'_> {
+        CachedRows {
+            input: Decoder {
+                columns: self.columns.map(|range| &self.map[range.clone()]),
+                strings: &self.strings,
+                guids: &self.guids,
+            },
+            remaining: self.rows,
+            line: 0,
+            offset: 0,
+            timestamp: 0,
+            corrupt: false,
+        }
+    }
+
+    pub fn parse(&self) -> ParsedLog<'_> {
+        self.rows().collect()
+    }
+}
+
+/// Iterator over the rows of a `CachedLog`, created by `CachedLog::rows`.
+///
+/// Rows borrow their strings from the `CachedLog`. Iteration stops early when
+/// the cache turns out to be corrupt, see `is_corrupt`.
+pub struct CachedRows<'a> {
+    input: Decoder<'a>,
+    remaining: u64,
+    line: usize,
+    offset: usize,
+    timestamp: i64,
+    corrupt: bool,
+}
+
+impl<'a> CachedRows<'a> {
+    /// Whether a row could not be decoded. The rows read before it are fine,
+    /// but the rest of the log has to be parsed again.
+    pub fn is_corrupt(&self) -> bool {
+        self.corrupt
+    }
+
+    fn decode(&mut self) -> Option<Result<Event<'a>, ParseError>> {
+        let input = &mut self.input;
+        match input.tag()? {
+            0 => {
+                self.line = usize::try_from(self.line as i64 + input.int()?).ok()?;
+                self.offset = usize::try_from(self.offset as i64 + input.int()?).ok()?;
+                self.timestamp += input.int()?;
+                Some(Ok(Event {
+                    line: self.line,
+                    offset: self.offset,
+                    date_time: Columnar::decode(input)?,
+                    timestamp: self.timestamp,
+                    row: Columnar::decode(input)?,
+                }))
+            }
+            1 => Some(Err(Columnar::decode(input)?)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Iterator for CachedRows<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.corrupt {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let row = self.decode();
+        self.corrupt = row.is_none();
+        row
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+
+    use super::*;
+    use crate::parser::LogFile;
+
+    #[test]
+    fn caches_parsed_log() {
+        let dir =
+            std::env::temp_dir().join(format!("wow-log-analyzer-cache-{}", std::process::id()));
+        let log = dir.join("WoWCombatLog.txt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &log,
+            "10/16/2026 20:15:03.123-4  COMBAT_LOG_VERSION,20,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,10.1.7,PROJECT_ID,1\n\
+            10/16/2026 20:15:03.500-4  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n\
+            10/16/2026 20:15:03.500-4  COMBATANT_INFO,Player-1379-0A9FF58F,1,1580,9210,41265,1590,0,0,0,1874,1874,1874,0,0,1120,1120,1120,0,2290,1030,1030,1030,6843,104,[(82210,102338,1),(82213,102341,2)],(0,0,0,0),[(202518,441,(),(6652,9230,9382,1498,8767),(192945,415)),(0,0,(),(),()),(204704,447,(6643,0,0),(6652,7980,1507,8767),())],[Player-1379-0A9FF58F,1126,Player-1379-0B0D2C10,381748],0,0,0,0\n\
+            10/16/2026 20:15:04.000-4  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2515-19964-196102-000550239A,0000000000000000,1483954,1952835,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,488,488,-1,8,0,0,0,nil,nil,nil\n\
+            10/16/2026 20:15:04.000-4  SPELL_DAMAGE,broken\n\
+            10/16/2026 20:15:05.250-4  SPELL_AURA_APPLIED,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,17,\"Power Word: Shield\",0x2,BUFF,120000\n\
+            10/16/2026 20:16:38.135-4  ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,0,95012\n",
+        )
+        .unwrap();
+
+        let cache = LogCache::new(dir.join("cache"));
+        assert!(cache.load(&log).is_none());
+
+        let file = LogFile::open(&log).unwrap();
+        let parsed: Vec<_> = file.rows().collect();
+        let mut writer = cache.writer(&log).unwrap();
+        for row in &parsed {
+            writer.push(row);
+        }
+        writer.finish().unwrap();
+
+        let cached = cache.load(&log).unwrap();
+        let mut rows = cached.rows();
+        assert_eq!(rows.by_ref().collect::<Vec<_>>(), parsed);
+        assert!(!rows.is_corrupt());
+        assert!(parsed[4].is_err());
+
+        // Logging more rows invalidates the cache.
+        let mut appended = OpenOptions::new().append(true).open(&log).unwrap();
+        writeln!(
+            appended,
+            "10/16/2026 20:16:40.000-4  ZONE_CHANGE,2569,\"Aberrus, the Shadowed Crucible\",16"
+        )
+        .unwrap();
+        assert!(cache.load(&log).is_none());
+
+        // The cache of a log that changed is pruned, files that are not caches are kept.
+        drop((cached, file));
+        let other = dir.join("cache").join("notes.txt");
+        fs::write(&other, "").unwrap();
+        assert_eq!(cache.prune().unwrap(), 1);
+        assert_eq!(fs::read_dir(dir.join("cache")).unwrap().count(), 1);
+        assert!(other.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
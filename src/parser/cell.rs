@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::str::{from_utf8, FromStr};
 
 use dioxus::prelude::SvgAttributes;
@@ -16,27 +17,52 @@ use nom::{
         complete::{double, float},
     },
     sequence::{delimited, pair, separated_pair, terminated, tuple},
-    Err, IResult, Parser,
+    Err, Parser,
 };
 
 use crate::parser;
 
+use super::combatant::{parse_combatant_info_line, CombatantInfo};
+use super::error::{IResult, RowError};
+use super::flags::{LogRowFlags, RaidFlags, UnitFlags};
+use super::guid::Guid;
+use super::layout::{LogLayout, DAMAGE_FIELDS};
+use super::school::SpellSchool;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum LogCell<'a> {
     Integer(i64),
     Float(f64),
     MultiPowerCell((i64, i64)),
-    Str(&'a str),
+    Str(Cow<'a, str>),
     Array(Vec<LogCell<'a>>),
 }
 
+impl<'a> LogCell<'a> {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            LogCell::Integer(v) => Some(*v),
+            LogCell::Float(v) => Some(*v as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            LogCell::Str(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> From<LogCell<'a>> for bool {
     fn from(cell: LogCell) -> Self {
         match cell {
             LogCell::Integer(v) => v != 0,
             LogCell::Float(v) => v != 0.0,
             LogCell::MultiPowerCell(v) => v.0 != 0,
-            LogCell::Str(v) => v != "",
+            // Boolean columns are written as `1` or `nil`.
+            LogCell::Str(v) => v != "" && v != "nil",
             LogCell::Array(v) => v.len() != 0,
         }
     }
@@ -47,165 +73,623 @@ pub enum LogRow<'a> {
     Emote(LogEmote<'a>),
     SpellCastSuccess(LogSpellCastSuccess<'a>),
     SpellDamage(LogSpellDamage<'a>),
+    // Wands, bows, guns and thrown weapons.
+    RangeDamage(LogSpellDamage<'a>),
     SpellHeal(LogSpellHeal<'a>),
+    SpellMissed(LogSpellMissed<'a>),
+    SpellEnergize(LogSpellEnergize<'a>),
+    SpellDrain(LogSpellDrain<'a>),
+    SpellLeech(LogSpellDrain<'a>),
+    SpellPeriodicDamage(LogSpellDamage<'a>),
+    SpellPeriodicHeal(LogSpellHeal<'a>),
+    SpellPeriodicMissed(LogSpellMissed<'a>),
+    SpellPeriodicEnergize(LogSpellEnergize<'a>),
+    SpellPeriodicDrain(LogSpellDrain<'a>),
+    SpellPeriodicLeech(LogSpellDrain<'a>),
+    SpellAuraApplied(LogSpellAura<'a>),
+    SpellAuraRemoved(LogSpellAura<'a>),
+    SpellAuraRefresh(LogSpellAura<'a>),
+    SpellAuraAppliedDose(LogSpellAura<'a>),
+    SpellAuraRemovedDose(LogSpellAura<'a>),
+    SpellAuraBroken(LogSpellAura<'a>),
+    SpellAuraBrokenSpell(LogSpellAuraBrokenSpell<'a>),
+    SpellAbsorbed(LogSpellAbsorbed<'a>),
+    // Pets, guardians and totems, with the player that summoned them as the source.
+    SpellSummon(LogSpellSummon<'a>),
+    // Objects such as traps, portals and feasts.
+    SpellCreate(LogSpellSummon<'a>),
+    CombatLogVersion(LogCombatLogVersion<'a>),
+    CombatantInfo(CombatantInfo),
+    UnitDied(LogUnitDied<'a>),
+    UnitDestroyed(LogUnitDied<'a>),
+    UnitDissipates(LogUnitDied<'a>),
+    PartyKill(LogUnitDied<'a>),
+    EncounterStart(LogEncounterStart<'a>),
+    EncounterEnd(LogEncounterEnd<'a>),
+    ZoneChange(LogZoneChange<'a>),
+    SwingDamage(LogSwingDamage<'a>),
+    SwingMissed(LogSwingMissed<'a>),
     NotSupported,
 }
 
+impl<'a> LogRow<'a> {
+    /// The unit and raid flags of the source and dest, for every row that has them.
+    pub fn flags(&self) -> Option<LogRowFlags> {
+        macro_rules! flags_of {
+            ($row:ident) => {
+                Some(LogRowFlags {
+                    source: $row.sourceFlags,
+                    sourceRaid: $row.sourceRaidFlags,
+                    dest: $row.destFlags,
+                    destRaid: $row.destRaidFlags,
+                })
+            };
+        }
+
+        match self {
+            LogRow::SpellCastSuccess(row) => flags_of!(row),
+            LogRow::SpellDamage(row)
+            | LogRow::SpellPeriodicDamage(row)
+            | LogRow::RangeDamage(row) => flags_of!(row),
+            LogRow::SpellHeal(row) | LogRow::SpellPeriodicHeal(row) => flags_of!(row),
+            LogRow::SpellMissed(row) | LogRow::SpellPeriodicMissed(row) => flags_of!(row),
+            LogRow::SpellEnergize(row) | LogRow::SpellPeriodicEnergize(row) => flags_of!(row),
+            LogRow::SpellDrain(row)
+            | LogRow::SpellLeech(row)
+            | LogRow::SpellPeriodicDrain(row)
+            | LogRow::SpellPeriodicLeech(row) => flags_of!(row),
+            LogRow::SpellAuraApplied(row)
+            | LogRow::SpellAuraRemoved(row)
+            | LogRow::SpellAuraRefresh(row)
+            | LogRow::SpellAuraAppliedDose(row)
+            | LogRow::SpellAuraRemovedDose(row)
+            | LogRow::SpellAuraBroken(row) => flags_of!(row),
+            LogRow::SpellAuraBrokenSpell(row) => flags_of!(row),
+            LogRow::SpellAbsorbed(row) => flags_of!(row),
+            LogRow::SpellSummon(row) | LogRow::SpellCreate(row) => flags_of!(row),
+            LogRow::UnitDied(row)
+            | LogRow::UnitDestroyed(row)
+            | LogRow::UnitDissipates(row)
+            | LogRow::PartyKill(row) => flags_of!(row),
+            LogRow::SwingDamage(row) => flags_of!(row),
+            LogRow::SwingMissed(row) => flags_of!(row),
+            _ => None,
+        }
+    }
+
+    pub fn source_flags(&self) -> Option<UnitFlags> {
+        self.flags().map(|flags| flags.source)
+    }
+
+    pub fn dest_flags(&self) -> Option<UnitFlags> {
+        self.flags().map(|flags| flags.dest)
+    }
+
+    /// The advanced params of every row that has them, when advanced logging is enabled.
+    pub fn advanced(&self) -> Option<&LogAdvancedParams<'a>> {
+        match self {
+            LogRow::SpellCastSuccess(row) => row.advanced.as_ref(),
+            LogRow::SpellDamage(row)
+            | LogRow::SpellPeriodicDamage(row)
+            | LogRow::RangeDamage(row) => row.advanced.as_ref(),
+            LogRow::SpellHeal(row) | LogRow::SpellPeriodicHeal(row) => row.advanced.as_ref(),
+            LogRow::SpellEnergize(row) | LogRow::SpellPeriodicEnergize(row) => {
+                row.advanced.as_ref()
+            }
+            LogRow::SpellDrain(row)
+            | LogRow::SpellLeech(row)
+            | LogRow::SpellPeriodicDrain(row)
+            | LogRow::SpellPeriodicLeech(row) => row.advanced.as_ref(),
+            LogRow::SwingDamage(row) => row.advanced.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The advanced params with the flags of the unit they describe, which is
+    /// the source or the dest of the row depending on the event.
+    pub fn advanced_unit(&self) -> Option<(&LogAdvancedParams<'a>, UnitFlags)> {
+        macro_rules! unit_of {
+            ($row:ident) => {
+                $row.advanced.as_ref().and_then(|advanced| {
+                    if advanced.unitGUID == $row.sourceGUID {
+                        Some((advanced, $row.sourceFlags))
+                    } else if advanced.unitGUID == $row.destGUID {
+                        Some((advanced, $row.destFlags))
+                    } else {
+                        None
+                    }
+                })
+            };
+        }
+
+        match self {
+            LogRow::SpellCastSuccess(row) => unit_of!(row),
+            LogRow::SpellDamage(row)
+            | LogRow::SpellPeriodicDamage(row)
+            | LogRow::RangeDamage(row) => unit_of!(row),
+            LogRow::SpellHeal(row) | LogRow::SpellPeriodicHeal(row) => unit_of!(row),
+            LogRow::SpellEnergize(row) | LogRow::SpellPeriodicEnergize(row) => unit_of!(row),
+            LogRow::SpellDrain(row)
+            | LogRow::SpellLeech(row)
+            | LogRow::SpellPeriodicDrain(row)
+            | LogRow::SpellPeriodicLeech(row) => unit_of!(row),
+            LogRow::SwingDamage(row) => unit_of!(row),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct LogEmote<'a> {
-    pub sourceGUID: &'a str,
-    pub sourcename: &'a str,
-    pub sourceflags: &'a str,
-    pub sourceraidflags: &'a str,
-    pub text: &'a str,
+    pub sourceGUID: Guid,
+    pub sourcename: Cow<'a, str>,
+    pub sourceflags: Cow<'a, str>,
+    pub sourceraidflags: Cow<'a, str>,
+    pub text: Cow<'a, str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogCombatLogVersion<'a> {
+    pub version: i64,
+    pub advancedLogEnabled: bool,
+    pub buildVersion: Cow<'a, str>,
+    pub projectID: i64,
+}
+
+// Only written when advanced combat logging is enabled. For spell and ranged
+// damage the unit described is the target. For melee swings, like every other
+// event, it is the source, i.e. the attacker.
+#[derive(Debug, PartialEq)]
+pub struct LogAdvancedParams<'a> {
+    pub unitGUID: Guid,
+    pub ownerGUID: Guid,
+    pub currHp: LogCell<'a>,
+    pub maxHp: LogCell<'a>,
+    pub attackPower: LogCell<'a>,
+    pub spellPower: LogCell<'a>,
+    pub armor: LogCell<'a>,
+    // Not written by logs older than version 20.
+    pub totalDamageAbsorbs: Option<LogCell<'a>>,
+    pub resourceType: LogCell<'a>,
+    pub currResource: LogCell<'a>,
+    pub maxResource: LogCell<'a>,
+    pub resourceCost: LogCell<'a>,
+    pub y: LogCell<'a>,
+    pub x: LogCell<'a>,
+    pub mapId: LogCell<'a>,
+    pub facing: LogCell<'a>,
+    pub ilvl: LogCell<'a>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogEncounterStart<'a> {
+    pub encounterID: LogCell<'a>,
+    pub encounterName: LogCell<'a>,
+    pub difficultyID: LogCell<'a>,
+    pub groupSize: LogCell<'a>,
+    pub instanceID: LogCell<'a>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogEncounterEnd<'a> {
+    pub encounterID: LogCell<'a>,
+    pub encounterName: LogCell<'a>,
+    pub difficultyID: LogCell<'a>,
+    pub groupSize: LogCell<'a>,
+    pub success: bool,
+    // Duration of the pull in milliseconds.
+    pub fightTime: LogCell<'a>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogZoneChange<'a> {
+    pub instanceID: LogCell<'a>,
+    pub zoneName: LogCell<'a>,
+    pub difficultyID: LogCell<'a>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct LogSpellCastSuccess<'a> {
-    sourceGUID: LogCell<'a>,
-    sourceName: LogCell<'a>,
-    sourceFlags: LogCell<'a>,
-    sourceRaidFlags: LogCell<'a>,
-    destGUID: LogCell<'a>,
-    destName: LogCell<'a>,
-    destFlags: LogCell<'a>,
-    destRaidFlags: LogCell<'a>,
-    spellId: LogCell<'a>,
-    spellName: LogCell<'a>,
-    spellSchool: LogCell<'a>,
-    unitGUID: LogCell<'a>,
-    ownerGUID: LogCell<'a>,
-    currHp: LogCell<'a>,
-    maxHp: LogCell<'a>,
-    attackPower: LogCell<'a>,
-    spellPower: LogCell<'a>,
-    armor: LogCell<'a>,
-    totalDamageAbsorbs: LogCell<'a>,
-    resourceType: LogCell<'a>,
-    currResource: LogCell<'a>,
-    maxResource: LogCell<'a>,
-    resourceCost: LogCell<'a>,
-    y: LogCell<'a>,
-    x: LogCell<'a>,
-    mapId: LogCell<'a>,
-    facing: LogCell<'a>,
-    ilvl: LogCell<'a>,
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    pub advanced: Option<LogAdvancedParams<'a>>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct LogSpellDamage<'a> {
-    sourceGUID: LogCell<'a>,
-    sourceName: LogCell<'a>,
-    sourceFlags: LogCell<'a>,
-    sourceRaidFlags: LogCell<'a>,
-    destGUID: LogCell<'a>,
-    destName: LogCell<'a>,
-    destFlags: LogCell<'a>,
-    destRaidFlags: LogCell<'a>,
-    spellId: LogCell<'a>,
-    spellName: LogCell<'a>,
-    spellSchool: LogCell<'a>,
-    unitGUID: LogCell<'a>,
-    ownerGUID: LogCell<'a>,
-    currHp: LogCell<'a>,
-    maxHp: LogCell<'a>,
-    attackPower: LogCell<'a>,
-    spellPower: LogCell<'a>,
-    armor: LogCell<'a>,
-    totalDamageAbsorbs: LogCell<'a>,
-    resourceType: LogCell<'a>,
-    currResource: LogCell<'a>,
-    maxResource: LogCell<'a>,
-    resourceCost: LogCell<'a>,
-    y: LogCell<'a>,
-    x: LogCell<'a>,
-    mapId: LogCell<'a>,
-    facing: LogCell<'a>,
-    ilvl: LogCell<'a>,
-    amount: LogCell<'a>,
-    overkill: LogCell<'a>,
-    school: LogCell<'a>,
-    resisted: LogCell<'a>,
-    blocked: LogCell<'a>,
-    absorbed: LogCell<'a>,
-    critical: bool,
-    glancing: bool,
-    crushing: bool,
-    isOffHand: bool,
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    pub advanced: Option<LogAdvancedParams<'a>>,
+    pub amount: LogCell<'a>,
+    // Amount before mitigation, written by logs from version 20 onwards.
+    pub baseAmount: Option<LogCell<'a>>,
+    pub overkill: LogCell<'a>,
+    pub school: SpellSchool,
+    pub resisted: LogCell<'a>,
+    pub blocked: LogCell<'a>,
+    pub absorbed: LogCell<'a>,
+    pub critical: bool,
+    pub glancing: bool,
+    pub crushing: bool,
+    // Replaced by baseAmount in logs from version 20 onwards.
+    pub isOffHand: bool,
+    // Set for SPELL_PERIODIC_DAMAGE ticks.
+    pub periodic: bool,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct LogSpellHeal<'a> {
-    sourceGUID: LogCell<'a>,
-    sourceName: LogCell<'a>,
-    sourceFlags: LogCell<'a>,
-    sourceRaidFlags: LogCell<'a>,
-    destGUID: LogCell<'a>,
-    destName: LogCell<'a>,
-    destFlags: LogCell<'a>,
-    destRaidFlags: LogCell<'a>,
-    spellId: LogCell<'a>,
-    spellName: LogCell<'a>,
-    spellSchool: LogCell<'a>,
-    unitGUID: LogCell<'a>,
-    ownerGUID: LogCell<'a>,
-    currHp: LogCell<'a>,
-    maxHp: LogCell<'a>,
-    attackPower: LogCell<'a>,
-    spellPower: LogCell<'a>,
-    armor: LogCell<'a>,
-    totalDamageAbsorbs: LogCell<'a>,
-    resourceType: LogCell<'a>,
-    currResource: LogCell<'a>,
-    maxResource: LogCell<'a>,
-    resourceCost: LogCell<'a>,
-    y: LogCell<'a>,
-    x: LogCell<'a>,
-    mapId: LogCell<'a>,
-    facing: LogCell<'a>,
-    ilvl: LogCell<'a>,
-    amount: LogCell<'a>,
-    overhealing: LogCell<'a>,
-    absorbed: LogCell<'a>,
-    critical: bool,
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    pub advanced: Option<LogAdvancedParams<'a>>,
+    pub amount: LogCell<'a>,
+    // Amount before mitigation, written by logs from version 20 onwards.
+    pub baseAmount: Option<LogCell<'a>>,
+    pub overhealing: LogCell<'a>,
+    pub absorbed: LogCell<'a>,
+    pub critical: bool,
+    // Set for SPELL_PERIODIC_HEAL ticks.
+    pub periodic: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogSpellMissed<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    pub missType: LogCell<'a>,
+    pub isOffHand: bool,
+    // Only present for ABSORB, BLOCK and RESIST misses.
+    pub amountMissed: Option<LogCell<'a>>,
+    pub critical: bool,
+    pub periodic: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogSpellEnergize<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    pub advanced: Option<LogAdvancedParams<'a>>,
+    pub amount: LogCell<'a>,
+    pub overEnergize: LogCell<'a>,
+    pub powerType: LogCell<'a>,
+    pub maxPower: LogCell<'a>,
+    pub periodic: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogSpellDrain<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    pub advanced: Option<LogAdvancedParams<'a>>,
+    pub amount: LogCell<'a>,
+    pub powerType: LogCell<'a>,
+    pub extraAmount: LogCell<'a>,
+    pub maxPower: LogCell<'a>,
+    pub periodic: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AuraType {
+    Buff,
+    Debuff,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogSpellAura<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    pub auraType: AuraType,
+    // Stack count for the _DOSE events, absorb amount for shields on APPLIED/REMOVED.
+    pub amount: Option<LogCell<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogSpellAuraBrokenSpell<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+    // The spell that broke the aura.
+    pub extraSpellId: LogCell<'a>,
+    pub extraSpellName: LogCell<'a>,
+    pub extraSchool: SpellSchool,
+    pub auraType: AuraType,
+}
+
+// Damage a shield absorbed. The source is the attacker, the dest the unit the
+// shield was on and the caster the unit that cast the shield.
+#[derive(Debug, PartialEq)]
+pub struct LogSpellAbsorbed<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    // The spell whose damage was absorbed, not written for melee swings.
+    pub spellId: Option<LogCell<'a>>,
+    pub spellName: Option<LogCell<'a>>,
+    pub spellSchool: Option<SpellSchool>,
+    pub casterGUID: Guid,
+    pub casterName: LogCell<'a>,
+    pub casterFlags: UnitFlags,
+    pub casterRaidFlags: RaidFlags,
+    pub absorbSpellId: LogCell<'a>,
+    pub absorbSpellName: LogCell<'a>,
+    pub absorbSpellSchool: SpellSchool,
+    pub amount: LogCell<'a>,
+    // The damage before it was absorbed, written by retail logs.
+    pub baseAmount: Option<LogCell<'a>>,
+    pub critical: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogSpellSummon<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub spellId: LogCell<'a>,
+    pub spellName: LogCell<'a>,
+    pub spellSchool: SpellSchool,
+}
+
+// Shared by UNIT_DIED, UNIT_DESTROYED, UNIT_DISSIPATES and PARTY_KILL. The dest
+// is the unit that died, the source is only set for PARTY_KILL.
+#[derive(Debug, PartialEq)]
+pub struct LogUnitDied<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    // Set when the unit is knocked out rather than killed, written from version 20.
+    pub unconsciousOnDeath: bool,
 }
 
 #[derive(Debug, PartialEq)]
-pub struct LogEventDateTime<'a> {
-    // The month an event occurred
-    pub month: &'a str,
-    // The day of the month an event occurred
-    pub day: &'a str,
-    // The hour an event occured
-    pub hour: &'a str,
-    // The minute an event occured
-    pub minute: &'a str,
-    // The second event occured
-    pub second: &'a str,
-    // The millisecond event occured
-    pub ms: &'a str,
+pub struct LogSwingDamage<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub advanced: Option<LogAdvancedParams<'a>>,
+    pub amount: LogCell<'a>,
+    // Amount before mitigation, written by logs from version 20 onwards.
+    pub baseAmount: Option<LogCell<'a>>,
+    pub overkill: LogCell<'a>,
+    pub school: SpellSchool,
+    pub resisted: LogCell<'a>,
+    pub blocked: LogCell<'a>,
+    pub absorbed: LogCell<'a>,
+    pub critical: bool,
+    pub glancing: bool,
+    pub crushing: bool,
+    // Replaced by baseAmount in logs from version 20 onwards.
+    pub isOffHand: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogSwingMissed<'a> {
+    pub sourceGUID: Guid,
+    pub sourceName: LogCell<'a>,
+    pub sourceFlags: UnitFlags,
+    pub sourceRaidFlags: RaidFlags,
+    pub destGUID: Guid,
+    pub destName: LogCell<'a>,
+    pub destFlags: UnitFlags,
+    pub destRaidFlags: RaidFlags,
+    pub missType: LogCell<'a>,
+    pub isOffHand: bool,
+    // Only present for ABSORB, BLOCK and RESIST misses.
+    pub amountMissed: Option<LogCell<'a>>,
+    pub critical: bool,
+}
+
+// The fields of every row struct, in declaration order, passed to `$callback`
+// to implement the traits that have to visit each field. Listing a field twice
+// or leaving one out is a compile error, so the lists cannot drift from the
+// structs.
+macro_rules! row_structs {
+    ($callback:ident) => {
+        $callback! {
+            LogEmote { sourceGUID, sourcename, sourceflags, sourceraidflags, text }
+            LogCombatLogVersion { version, advancedLogEnabled, buildVersion, projectID }
+            LogAdvancedParams {
+                unitGUID, ownerGUID, currHp, maxHp, attackPower, spellPower, armor,
+                totalDamageAbsorbs,
+                resourceType, currResource, maxResource, resourceCost, y, x, mapId, facing, ilvl,
+            }
+            LogEncounterStart { encounterID, encounterName, difficultyID, groupSize, instanceID }
+            LogEncounterEnd {
+                encounterID, encounterName, difficultyID, groupSize, success, fightTime,
+            }
+            LogZoneChange { instanceID, zoneName, difficultyID }
+            LogSpellCastSuccess {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool, advanced,
+            }
+            LogSpellDamage {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool, advanced,
+                amount, baseAmount, overkill, school, resisted, blocked, absorbed,
+                critical, glancing, crushing, isOffHand, periodic,
+            }
+            LogSpellHeal {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool, advanced,
+                amount, baseAmount, overhealing, absorbed, critical, periodic,
+            }
+            LogSpellMissed {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool,
+                missType, isOffHand, amountMissed, critical, periodic,
+            }
+            LogSpellEnergize {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool, advanced,
+                amount, overEnergize, powerType, maxPower, periodic,
+            }
+            LogSpellDrain {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool, advanced,
+                amount, powerType, extraAmount, maxPower, periodic,
+            }
+            LogSpellAura {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool, auraType, amount,
+            }
+            LogSpellAuraBrokenSpell {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool,
+                extraSpellId, extraSpellName, extraSchool, auraType,
+            }
+            LogSpellAbsorbed {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool,
+                casterGUID, casterName, casterFlags, casterRaidFlags,
+                absorbSpellId, absorbSpellName, absorbSpellSchool, amount, baseAmount, critical,
+            }
+            LogSpellSummon {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                spellId, spellName, spellSchool,
+            }
+            LogUnitDied {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                unconsciousOnDeath,
+            }
+            LogSwingDamage {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                advanced,
+                amount, baseAmount, overkill, school, resisted, blocked, absorbed,
+                critical, glancing, crushing, isOffHand,
+            }
+            LogSwingMissed {
+                sourceGUID, sourceName, sourceFlags, sourceRaidFlags,
+                destGUID, destName, destFlags, destRaidFlags,
+                missType, isOffHand, amountMissed, critical,
+            }
+        }
+    };
 }
 
+pub(super) use row_structs;
+
 pub fn parse_log_cell(input: &str) -> IResult<&str, LogCell> {
     if input.len() == 1 {
-        return match &input[0..1] {
+        return match input.get(0..1).unwrap_or_default() {
             "[" => parse_array(input, "[".to_string(), "]".to_string()),
             "(" => parse_array(input, "(".to_string(), ")".to_string()),
-            "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "-" => parse_integer(input),
+            "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "-" => parse_integer(input),
             _ => parse_string(input),
         };
     }
-    match &input[0..1] {
+    match input.get(0..1).unwrap_or_default() {
         "[" => parse_array(input, "[".to_string(), "]".to_string()),
         "(" => parse_array(input, "(".to_string(), ")".to_string()),
-        "0" => match &input[0..2] {
+        "0" => match input.get(0..2).unwrap_or_default() {
             "0x" => {
                 let parser = tuple((tag("0x"), alphanumeric1));
-                map(parser, |(_, v)| LogCell::Str(v))(input)
+                map(parser, |(_, v)| LogCell::Str(Cow::Borrowed(v)))(input)
             }
             _ => parse_number(input),
         },
-        "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "-" => parse_number(input),
+        "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "-" => parse_number(input),
         _ => parse_string(input),
     }
 }
@@ -225,18 +709,20 @@ pub fn parse_array(
 }
 
 pub fn parse_string(input: &str) -> IResult<&str, LogCell> {
-    match &input[0..1] {
+    match input.get(0..1).unwrap_or_default() {
         "|" => {
             let parser = delimited(tag("|T"), take_while1(is_valid_emote), tag("!"));
 
-            map(parser, |v| LogCell::Str(v))(input)
+            map(parser, |v| LogCell::Str(Cow::Borrowed(v)))(input)
         }
         "\"" => {
             let parser = delimited(tag("\""), take_while1(is_valid_wrapped), tag("\""));
 
-            map(parser, |v| LogCell::Str(v))(input)
+            map(parser, |v| LogCell::Str(Cow::Borrowed(v)))(input)
         }
-        _ => map(take_while1(is_valid_unwrapped), |v| LogCell::Str(v))(input),
+        _ => map(take_while1(is_valid_unwrapped), |v| {
+            LogCell::Str(Cow::Borrowed(v))
+        })(input),
     }
 }
 
@@ -287,25 +773,155 @@ pub fn is_valid_unwrapped(c: char) -> bool {
     (cv >= 0x20) && (cv != 0x22) && (cv != 0x5C) && (cv != 0x5D) && (cv != 0x2C) && (cv != 0x29)
 }
 
-pub fn parse_log_csv(input: &str) -> IResult<&str, LogRow> {
-    let (eventtype, _) = input.split_once(",").unwrap();
+pub fn parse_log_csv<'a>(input: &'a str, layout: &LogLayout) -> IResult<&'a str, LogRow<'a>> {
+    let eventtype = input.split(',').next().unwrap_or_default();
     let res = match eventtype {
+        "COMBAT_LOG_VERSION" => {
+            let (remainder, cell) = parse_combat_log_version_line(input)?;
+            Ok((remainder, LogRow::CombatLogVersion(cell)))
+        }
+        "COMBATANT_INFO" => {
+            let (remainder, cell) = parse_combatant_info_line(input)?;
+            Ok((remainder, LogRow::CombatantInfo(cell)))
+        }
         "EMOTE" => {
             let (remainder, cell) = parse_emote_line(input)?;
             Ok((remainder, LogRow::Emote(cell)))
         }
         "SPELL_CAST_SUCCESS" => {
-            let (remainder, cell) = parse_spell_cast_success_line(input)?;
+            let (remainder, cell) = parse_spell_cast_success_line(input, layout)?;
             Ok((remainder, LogRow::SpellCastSuccess(cell)))
         }
         "SPELL_DAMAGE" => {
-            let (remainder, cell) = parse_spell_damage_line(input)?;
+            let (remainder, cell) = parse_spell_damage_line(input, layout)?;
             Ok((remainder, LogRow::SpellDamage(cell)))
         }
+        "RANGE_DAMAGE" => {
+            let (remainder, cell) = parse_spell_damage_event("RANGE_DAMAGE", input, layout)?;
+            Ok((remainder, LogRow::RangeDamage(cell)))
+        }
         "SPELL_HEAL" => {
-            let (remainder, cell) = parse_spell_heal_line(input)?;
+            let (remainder, cell) = parse_spell_heal_line(input, layout)?;
             Ok((remainder, LogRow::SpellHeal(cell)))
         }
+        "SPELL_MISSED" => {
+            let (remainder, cell) = parse_spell_missed_line("SPELL_MISSED", input, layout)?;
+            Ok((remainder, LogRow::SpellMissed(cell)))
+        }
+        "SPELL_ENERGIZE" => {
+            let (remainder, cell) = parse_spell_energize_line("SPELL_ENERGIZE", input, layout)?;
+            Ok((remainder, LogRow::SpellEnergize(cell)))
+        }
+        "SPELL_DRAIN" => {
+            let (remainder, cell) = parse_spell_drain_line("SPELL_DRAIN", input, layout)?;
+            Ok((remainder, LogRow::SpellDrain(cell)))
+        }
+        "SPELL_LEECH" => {
+            let (remainder, cell) = parse_spell_drain_line("SPELL_LEECH", input, layout)?;
+            Ok((remainder, LogRow::SpellLeech(cell)))
+        }
+        "SPELL_PERIODIC_DAMAGE" => {
+            let (remainder, cell) = parse_spell_periodic_damage_line(input, layout)?;
+            Ok((remainder, LogRow::SpellPeriodicDamage(cell)))
+        }
+        "SPELL_PERIODIC_HEAL" => {
+            let (remainder, cell) = parse_spell_periodic_heal_line(input, layout)?;
+            Ok((remainder, LogRow::SpellPeriodicHeal(cell)))
+        }
+        "SPELL_PERIODIC_MISSED" => {
+            let (remainder, cell) =
+                parse_spell_missed_line("SPELL_PERIODIC_MISSED", input, layout)?;
+            Ok((remainder, LogRow::SpellPeriodicMissed(cell)))
+        }
+        "SPELL_PERIODIC_ENERGIZE" => {
+            let (remainder, cell) =
+                parse_spell_energize_line("SPELL_PERIODIC_ENERGIZE", input, layout)?;
+            Ok((remainder, LogRow::SpellPeriodicEnergize(cell)))
+        }
+        "SPELL_PERIODIC_DRAIN" => {
+            let (remainder, cell) = parse_spell_drain_line("SPELL_PERIODIC_DRAIN", input, layout)?;
+            Ok((remainder, LogRow::SpellPeriodicDrain(cell)))
+        }
+        "SPELL_PERIODIC_LEECH" => {
+            let (remainder, cell) = parse_spell_drain_line("SPELL_PERIODIC_LEECH", input, layout)?;
+            Ok((remainder, LogRow::SpellPeriodicLeech(cell)))
+        }
+        "SWING_DAMAGE" => {
+            let (remainder, cell) = parse_swing_damage_line(input, layout)?;
+            Ok((remainder, LogRow::SwingDamage(cell)))
+        }
+        "SWING_MISSED" => {
+            let (remainder, cell) = parse_swing_missed_line(input, layout)?;
+            Ok((remainder, LogRow::SwingMissed(cell)))
+        }
+        "SPELL_AURA_APPLIED" => {
+            let (remainder, cell) = parse_spell_aura_line("SPELL_AURA_APPLIED", input)?;
+            Ok((remainder, LogRow::SpellAuraApplied(cell)))
+        }
+        "SPELL_AURA_REMOVED" => {
+            let (remainder, cell) = parse_spell_aura_line("SPELL_AURA_REMOVED", input)?;
+            Ok((remainder, LogRow::SpellAuraRemoved(cell)))
+        }
+        "SPELL_AURA_REFRESH" => {
+            let (remainder, cell) = parse_spell_aura_line("SPELL_AURA_REFRESH", input)?;
+            Ok((remainder, LogRow::SpellAuraRefresh(cell)))
+        }
+        "SPELL_AURA_APPLIED_DOSE" => {
+            let (remainder, cell) = parse_spell_aura_line("SPELL_AURA_APPLIED_DOSE", input)?;
+            Ok((remainder, LogRow::SpellAuraAppliedDose(cell)))
+        }
+        "SPELL_AURA_REMOVED_DOSE" => {
+            let (remainder, cell) = parse_spell_aura_line("SPELL_AURA_REMOVED_DOSE", input)?;
+            Ok((remainder, LogRow::SpellAuraRemovedDose(cell)))
+        }
+        "SPELL_AURA_BROKEN" => {
+            let (remainder, cell) = parse_spell_aura_line("SPELL_AURA_BROKEN", input)?;
+            Ok((remainder, LogRow::SpellAuraBroken(cell)))
+        }
+        "SPELL_AURA_BROKEN_SPELL" => {
+            let (remainder, cell) = parse_spell_aura_broken_spell_line(input)?;
+            Ok((remainder, LogRow::SpellAuraBrokenSpell(cell)))
+        }
+        "SPELL_ABSORBED" => {
+            let (remainder, cell) = parse_spell_absorbed_line(input)?;
+            Ok((remainder, LogRow::SpellAbsorbed(cell)))
+        }
+        "SPELL_SUMMON" => {
+            let (remainder, cell) = parse_spell_summon_line("SPELL_SUMMON", input)?;
+            Ok((remainder, LogRow::SpellSummon(cell)))
+        }
+        "SPELL_CREATE" => {
+            let (remainder, cell) = parse_spell_summon_line("SPELL_CREATE", input)?;
+            Ok((remainder, LogRow::SpellCreate(cell)))
+        }
+        "ENCOUNTER_START" => {
+            let (remainder, cell) = parse_encounter_start_line(input)?;
+            Ok((remainder, LogRow::EncounterStart(cell)))
+        }
+        "ENCOUNTER_END" => {
+            let (remainder, cell) = parse_encounter_end_line(input)?;
+            Ok((remainder, LogRow::EncounterEnd(cell)))
+        }
+        "ZONE_CHANGE" => {
+            let (remainder, cell) = parse_zone_change_line(input)?;
+            Ok((remainder, LogRow::ZoneChange(cell)))
+        }
+        "UNIT_DIED" => {
+            let (remainder, cell) = parse_unit_died_line("UNIT_DIED", input)?;
+            Ok((remainder, LogRow::UnitDied(cell)))
+        }
+        "UNIT_DESTROYED" => {
+            let (remainder, cell) = parse_unit_died_line("UNIT_DESTROYED", input)?;
+            Ok((remainder, LogRow::UnitDestroyed(cell)))
+        }
+        "UNIT_DISSIPATES" => {
+            let (remainder, cell) = parse_unit_died_line("UNIT_DISSIPATES", input)?;
+            Ok((remainder, LogRow::UnitDissipates(cell)))
+        }
+        "PARTY_KILL" => {
+            let (remainder, cell) = parse_unit_died_line("PARTY_KILL", input)?;
+            Ok((remainder, LogRow::PartyKill(cell)))
+        }
         _ => Ok((input, LogRow::NotSupported)),
     };
     res
@@ -329,16 +945,74 @@ fn parse_emote_fields(
 
 pub fn parse_emote_line(input: &str) -> IResult<&str, LogEmote> {
     map(parse_emote_fields, |emote_tuple| LogEmote {
-        sourceGUID: emote_tuple.0,
-        sourcename: emote_tuple.2,
-        sourceflags: emote_tuple.4,
-        sourceraidflags: emote_tuple.6,
-        text: emote_tuple.8,
+        sourceGUID: Guid::from(emote_tuple.0),
+        sourcename: emote_tuple.2.into(),
+        sourceflags: emote_tuple.4.into(),
+        sourceraidflags: emote_tuple.6.into(),
+        text: emote_tuple.8.into(),
     })
     .parse(input)
 }
 
-pub fn parse_spell_cast_success_line(input: &str) -> IResult<&str, LogSpellCastSuccess> {
+pub fn parse_combat_log_version_line(input: &str) -> IResult<&str, LogCombatLogVersion> {
+    let parser = tuple((
+        tag("COMBAT_LOG_VERSION,"),
+        map_res(digit1, str::parse),
+        tag(",ADVANCED_LOG_ENABLED,"),
+        digit1,
+        tag(",BUILD_VERSION,"),
+        take_while1(|c| c != ','),
+        tag(",PROJECT_ID,"),
+        map_res(digit1, str::parse),
+    ));
+
+    map(parser, |(_, version, _, advanced, _, build, _, project)| {
+        LogCombatLogVersion {
+            version,
+            advancedLogEnabled: advanced != "0",
+            buildVersion: build.into(),
+            projectID: project,
+        }
+    })(input)
+}
+
+fn parse_advanced_params<'a>(
+    cols_iter: &mut impl Iterator<Item = LogCell<'a>>,
+    layout: &LogLayout,
+) -> Option<LogAdvancedParams<'a>> {
+    if !layout.advanced {
+        return None;
+    }
+
+    Some(LogAdvancedParams {
+        unitGUID: cols_iter.next().unwrap().into(),
+        ownerGUID: cols_iter.next().unwrap().into(),
+        currHp: cols_iter.next().unwrap(),
+        maxHp: cols_iter.next().unwrap(),
+        attackPower: cols_iter.next().unwrap(),
+        spellPower: cols_iter.next().unwrap(),
+        armor: cols_iter.next().unwrap(),
+        totalDamageAbsorbs: if layout.has_total_absorbs() {
+            cols_iter.next()
+        } else {
+            None
+        },
+        resourceType: cols_iter.next().unwrap(),
+        currResource: cols_iter.next().unwrap(),
+        maxResource: cols_iter.next().unwrap(),
+        resourceCost: cols_iter.next().unwrap(),
+        y: cols_iter.next().unwrap(),
+        x: cols_iter.next().unwrap(),
+        mapId: cols_iter.next().unwrap(),
+        facing: cols_iter.next().unwrap(),
+        ilvl: cols_iter.next().unwrap(),
+    })
+}
+
+pub fn parse_spell_cast_success_line<'a>(
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellCastSuccess<'a>> {
     let (remainder, (event, _, cols)) = tuple((
         tag("SPELL_CAST_SUCCESS"),
         tag(","),
@@ -346,19 +1020,12 @@ pub fn parse_spell_cast_success_line(input: &str) -> IResult<&str, LogSpellCastS
     ))(input)?;
 
     if event != "SPELL_CAST_SUCCESS" {
-        return Err(Err::Error(nom::error::Error {
-            input,
-            code: ErrorKind::Tag,
-        }));
-    }
-
-    if cols.len() != 28 {
-        panic!(
-            "Spell cast success event malformed. Should have 28 fields, had: {:?}. cols: {:?}. input: {:?}",
-            cols.len(),
-            cols,
-            input
-        );
+        return Err(RowError::new(input, ErrorKind::Tag));
+    }
+
+    let expected = 11 + layout.advanced_fields();
+    if cols.len() != expected {
+        return Err(RowError::field_count(input, &[expected], cols.len()));
     }
 
     let mut cols_iter = cols.into_iter();
@@ -366,60 +1033,54 @@ pub fn parse_spell_cast_success_line(input: &str) -> IResult<&str, LogSpellCastS
     Ok((
         remainder,
         LogSpellCastSuccess {
-            sourceGUID: cols_iter.next().unwrap(),
+            sourceGUID: cols_iter.next().unwrap().into(),
             sourceName: cols_iter.next().unwrap(),
-            sourceFlags: cols_iter.next().unwrap(),
-            sourceRaidFlags: cols_iter.next().unwrap(),
-            destGUID: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
             destName: cols_iter.next().unwrap(),
-            destFlags: cols_iter.next().unwrap(),
-            destRaidFlags: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
             spellId: cols_iter.next().unwrap(),
             spellName: cols_iter.next().unwrap(),
-            spellSchool: cols_iter.next().unwrap(),
-            unitGUID: cols_iter.next().unwrap(),
-            ownerGUID: cols_iter.next().unwrap(),
-            currHp: cols_iter.next().unwrap(),
-            maxHp: cols_iter.next().unwrap(),
-            attackPower: cols_iter.next().unwrap(),
-            spellPower: cols_iter.next().unwrap(),
-            armor: cols_iter.next().unwrap(),
-            totalDamageAbsorbs: cols_iter.next().unwrap(),
-            resourceType: cols_iter.next().unwrap(),
-            currResource: cols_iter.next().unwrap(),
-            maxResource: cols_iter.next().unwrap(),
-            resourceCost: cols_iter.next().unwrap(),
-            y: cols_iter.next().unwrap(),
-            x: cols_iter.next().unwrap(),
-            mapId: cols_iter.next().unwrap(),
-            facing: cols_iter.next().unwrap(),
-            ilvl: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            advanced: parse_advanced_params(&mut cols_iter, layout),
         },
     ))
 }
 
-pub fn parse_spell_damage_line(input: &str) -> IResult<&str, LogSpellDamage> {
+pub fn parse_spell_damage_line<'a>(
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellDamage<'a>> {
+    parse_spell_damage_event("SPELL_DAMAGE", input, layout)
+}
+
+pub fn parse_spell_periodic_damage_line<'a>(
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellDamage<'a>> {
+    parse_spell_damage_event("SPELL_PERIODIC_DAMAGE", input, layout)
+}
+
+fn parse_spell_damage_event<'a>(
+    event_name: &'static str,
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellDamage<'a>> {
     let (remainder, (event, _, cols)) = tuple((
-        tag("SPELL_DAMAGE"),
+        tag(event_name),
         tag(","),
         separated_list1(tag(","), parse_log_cell),
     ))(input)?;
 
-    if event != "SPELL_DAMAGE" {
-        println!("Failed to parse event: {:?}", event);
-        return Err(Err::Error(nom::error::Error {
-            input,
-            code: ErrorKind::Tag,
-        }));
+    if event != event_name {
+        return Err(RowError::new(input, ErrorKind::Tag));
     }
 
-    if cols.len() != 38 {
-        panic!(
-            "Spell damage event malformed. Should have 38 fields, had: {:?}. cols: {:?}. input: {:?}",
-            cols.len(),
-            cols,
-            input
-        );
+    let expected = 11 + layout.advanced_fields() + DAMAGE_FIELDS;
+    if cols.len() != expected {
+        return Err(RowError::field_count(input, &[expected], cols.len()));
     }
 
     let mut cols_iter = cols.into_iter();
@@ -427,70 +1088,69 @@ pub fn parse_spell_damage_line(input: &str) -> IResult<&str, LogSpellDamage> {
     Ok((
         remainder,
         LogSpellDamage {
-            sourceGUID: cols_iter.next().unwrap(),
+            sourceGUID: cols_iter.next().unwrap().into(),
             sourceName: cols_iter.next().unwrap(),
-            sourceFlags: cols_iter.next().unwrap(),
-            sourceRaidFlags: cols_iter.next().unwrap(),
-            destGUID: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
             destName: cols_iter.next().unwrap(),
-            destFlags: cols_iter.next().unwrap(),
-            destRaidFlags: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
             spellId: cols_iter.next().unwrap(),
             spellName: cols_iter.next().unwrap(),
-            spellSchool: cols_iter.next().unwrap(),
-            unitGUID: cols_iter.next().unwrap(),
-            ownerGUID: cols_iter.next().unwrap(),
-            currHp: cols_iter.next().unwrap(),
-            maxHp: cols_iter.next().unwrap(),
-            attackPower: cols_iter.next().unwrap(),
-            spellPower: cols_iter.next().unwrap(),
-            armor: cols_iter.next().unwrap(),
-            totalDamageAbsorbs: cols_iter.next().unwrap(),
-            resourceType: cols_iter.next().unwrap(),
-            currResource: cols_iter.next().unwrap(),
-            maxResource: cols_iter.next().unwrap(),
-            resourceCost: cols_iter.next().unwrap(),
-            y: cols_iter.next().unwrap(),
-            x: cols_iter.next().unwrap(),
-            mapId: cols_iter.next().unwrap(),
-            facing: cols_iter.next().unwrap(),
-            ilvl: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            advanced: parse_advanced_params(&mut cols_iter, layout),
             amount: cols_iter.next().unwrap(),
+            baseAmount: if layout.has_base_amount() {
+                cols_iter.next()
+            } else {
+                None
+            },
             overkill: cols_iter.next().unwrap(),
-            school: cols_iter.next().unwrap(),
+            school: cols_iter.next().unwrap().into(),
             resisted: cols_iter.next().unwrap(),
             blocked: cols_iter.next().unwrap(),
             absorbed: cols_iter.next().unwrap(),
             critical: cols_iter.next().unwrap().into(),
             glancing: cols_iter.next().unwrap().into(),
             crushing: cols_iter.next().unwrap().into(),
-            isOffHand: cols_iter.next().unwrap().into(),
+            isOffHand: !layout.has_base_amount() && cols_iter.next().unwrap().into(),
+            periodic: event_name == "SPELL_PERIODIC_DAMAGE",
         },
     ))
 }
 
-pub fn parse_spell_heal_line(input: &str) -> IResult<&str, LogSpellHeal> {
+pub fn parse_spell_heal_line<'a>(
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellHeal<'a>> {
+    parse_spell_heal_event("SPELL_HEAL", input, layout)
+}
+
+pub fn parse_spell_periodic_heal_line<'a>(
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellHeal<'a>> {
+    parse_spell_heal_event("SPELL_PERIODIC_HEAL", input, layout)
+}
+
+fn parse_spell_heal_event<'a>(
+    event_name: &'static str,
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellHeal<'a>> {
     let (remainder, (event, _, cols)) = tuple((
-        tag("SPELL_HEAL"),
+        tag(event_name),
         tag(","),
         separated_list1(tag(","), parse_log_cell),
     ))(input)?;
 
-    if event != "SPELL_HEAL" {
-        return Err(Err::Error(nom::error::Error {
-            input,
-            code: ErrorKind::Tag,
-        }));
+    if event != event_name {
+        return Err(RowError::new(input, ErrorKind::Tag));
     }
-    if cols.len() != 33 {
-        println!(
-            "Spell heal event malformed. Should have 33 fields, had: {:?}",
-            cols.len()
-        );
-        return Err(Err::Error(nom::error::Error {
-            input,
-            code: ErrorKind::LengthValue,
-        }));
+    let expected = 11 + layout.advanced_fields() + layout.heal_fields();
+    if cols.len() != expected {
+        return Err(RowError::field_count(input, &[expected], cols.len()));
     }
 
     let mut cols_iter = cols.into_iter();
@@ -498,39 +1158,525 @@ pub fn parse_spell_heal_line(input: &str) -> IResult<&str, LogSpellHeal> {
     Ok((
         remainder,
         LogSpellHeal {
-            sourceGUID: cols_iter.next().unwrap(),
+            sourceGUID: cols_iter.next().unwrap().into(),
             sourceName: cols_iter.next().unwrap(),
-            sourceFlags: cols_iter.next().unwrap(),
-            sourceRaidFlags: cols_iter.next().unwrap(),
-            destGUID: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
             destName: cols_iter.next().unwrap(),
-            destFlags: cols_iter.next().unwrap(),
-            destRaidFlags: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
             spellId: cols_iter.next().unwrap(),
             spellName: cols_iter.next().unwrap(),
-            spellSchool: cols_iter.next().unwrap(),
-            unitGUID: cols_iter.next().unwrap(),
-            ownerGUID: cols_iter.next().unwrap(),
-            currHp: cols_iter.next().unwrap(),
-            maxHp: cols_iter.next().unwrap(),
-            attackPower: cols_iter.next().unwrap(),
-            spellPower: cols_iter.next().unwrap(),
-            armor: cols_iter.next().unwrap(),
-            totalDamageAbsorbs: cols_iter.next().unwrap(),
-            resourceType: cols_iter.next().unwrap(),
-            currResource: cols_iter.next().unwrap(),
-            maxResource: cols_iter.next().unwrap(),
-            resourceCost: cols_iter.next().unwrap(),
-            y: cols_iter.next().unwrap(),
-            x: cols_iter.next().unwrap(),
-            mapId: cols_iter.next().unwrap(),
-            facing: cols_iter.next().unwrap(),
-            ilvl: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            advanced: parse_advanced_params(&mut cols_iter, layout),
             amount: cols_iter.next().unwrap(),
+            baseAmount: if layout.has_base_amount() {
+                cols_iter.next()
+            } else {
+                None
+            },
             overhealing: cols_iter.next().unwrap(),
             absorbed: cols_iter.next().unwrap(),
             critical: cols_iter.next().unwrap().into(),
-            // Last field is always nil.
+            periodic: event_name == "SPELL_PERIODIC_HEAL",
+        },
+    ))
+}
+
+pub fn parse_spell_missed_line<'a>(
+    event_name: &'static str,
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellMissed<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag(event_name),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    // Same shapes as SWING_MISSED, with the spell prefix in front of the suffix.
+    let partial = 13 + layout.partial_miss_fields();
+    if cols.len() != 13 && cols.len() != partial {
+        return Err(RowError::field_count(input, &[13, partial], cols.len()));
+    }
+
+    let has_amount = cols.len() == partial;
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSpellMissed {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            spellId: cols_iter.next().unwrap(),
+            spellName: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            missType: cols_iter.next().unwrap(),
+            isOffHand: cols_iter.next().unwrap().into(),
+            amountMissed: if has_amount { cols_iter.next() } else { None },
+            critical: has_amount && parse_miss_critical(&mut cols_iter, layout),
+            periodic: event_name == "SPELL_PERIODIC_MISSED",
+        },
+    ))
+}
+
+pub fn parse_spell_energize_line<'a>(
+    event_name: &'static str,
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellEnergize<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag(event_name),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    let expected = 15 + layout.advanced_fields();
+    if cols.len() != expected {
+        return Err(RowError::field_count(input, &[expected], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSpellEnergize {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            spellId: cols_iter.next().unwrap(),
+            spellName: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            advanced: parse_advanced_params(&mut cols_iter, layout),
+            amount: cols_iter.next().unwrap(),
+            overEnergize: cols_iter.next().unwrap(),
+            powerType: cols_iter.next().unwrap(),
+            maxPower: cols_iter.next().unwrap(),
+            periodic: event_name == "SPELL_PERIODIC_ENERGIZE",
+        },
+    ))
+}
+
+pub fn parse_spell_drain_line<'a>(
+    event_name: &'static str,
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSpellDrain<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag(event_name),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    let expected = 15 + layout.advanced_fields();
+    if cols.len() != expected {
+        return Err(RowError::field_count(input, &[expected], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSpellDrain {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            spellId: cols_iter.next().unwrap(),
+            spellName: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            advanced: parse_advanced_params(&mut cols_iter, layout),
+            amount: cols_iter.next().unwrap(),
+            powerType: cols_iter.next().unwrap(),
+            extraAmount: cols_iter.next().unwrap(),
+            maxPower: cols_iter.next().unwrap(),
+            periodic: event_name.starts_with("SPELL_PERIODIC_"),
+        },
+    ))
+}
+
+pub fn parse_swing_damage_line<'a>(
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSwingDamage<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("SWING_DAMAGE"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    let expected = 8 + layout.advanced_fields() + DAMAGE_FIELDS;
+    if cols.len() != expected {
+        return Err(RowError::field_count(input, &[expected], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSwingDamage {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            advanced: parse_advanced_params(&mut cols_iter, layout),
+            amount: cols_iter.next().unwrap(),
+            baseAmount: if layout.has_base_amount() {
+                cols_iter.next()
+            } else {
+                None
+            },
+            overkill: cols_iter.next().unwrap(),
+            school: cols_iter.next().unwrap().into(),
+            resisted: cols_iter.next().unwrap(),
+            blocked: cols_iter.next().unwrap(),
+            absorbed: cols_iter.next().unwrap(),
+            critical: cols_iter.next().unwrap().into(),
+            glancing: cols_iter.next().unwrap().into(),
+            crushing: cols_iter.next().unwrap().into(),
+            isOffHand: !layout.has_base_amount() && cols_iter.next().unwrap().into(),
+        },
+    ))
+}
+
+// The crit flag of a partial miss, after the unmitigated amount in logs that
+// write it.
+fn parse_miss_critical<'a>(
+    cols: &mut impl Iterator<Item = LogCell<'a>>,
+    layout: &LogLayout,
+) -> bool {
+    let skipped = if layout.has_base_amount() { 1 } else { 0 };
+    cols.nth(skipped).map(bool::from).unwrap_or(false)
+}
+
+pub fn parse_swing_missed_line<'a>(
+    input: &'a str,
+    layout: &LogLayout,
+) -> IResult<&'a str, LogSwingMissed<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("SWING_MISSED"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    // Plain misses (DODGE, PARRY, ...) only carry the miss type and offhand flag,
+    // partial misses (ABSORB, BLOCK, RESIST) also carry the amount and crit flag.
+    let partial = 10 + layout.partial_miss_fields();
+    if cols.len() != 10 && cols.len() != partial {
+        return Err(RowError::field_count(input, &[10, partial], cols.len()));
+    }
+
+    let has_amount = cols.len() == partial;
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSwingMissed {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            missType: cols_iter.next().unwrap(),
+            isOffHand: cols_iter.next().unwrap().into(),
+            amountMissed: if has_amount { cols_iter.next() } else { None },
+            critical: has_amount && parse_miss_critical(&mut cols_iter, layout),
+        },
+    ))
+}
+
+fn parse_aura_type<'a>(input: &'a str, cell: LogCell) -> Result<AuraType, Err<RowError<&'a str>>> {
+    match cell.as_str() {
+        Some("BUFF") => Ok(AuraType::Buff),
+        Some("DEBUFF") => Ok(AuraType::Debuff),
+        _ => Err(RowError::new(input, ErrorKind::Tag)),
+    }
+}
+
+pub fn parse_spell_aura_line<'a>(
+    event_name: &'static str,
+    input: &'a str,
+) -> IResult<&'a str, LogSpellAura<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag(event_name),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() != 12 && cols.len() != 13 {
+        return Err(RowError::field_count(input, &[12, 13], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSpellAura {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            spellId: cols_iter.next().unwrap(),
+            spellName: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            auraType: parse_aura_type(input, cols_iter.next().unwrap())?,
+            amount: cols_iter.next(),
+        },
+    ))
+}
+
+pub fn parse_spell_aura_broken_spell_line(
+    input: &str,
+) -> IResult<&str, LogSpellAuraBrokenSpell<'_>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("SPELL_AURA_BROKEN_SPELL"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() != 15 {
+        return Err(RowError::field_count(input, &[15], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSpellAuraBrokenSpell {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            spellId: cols_iter.next().unwrap(),
+            spellName: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+            extraSpellId: cols_iter.next().unwrap(),
+            extraSpellName: cols_iter.next().unwrap(),
+            extraSchool: cols_iter.next().unwrap().into(),
+            auraType: parse_aura_type(input, cols_iter.next().unwrap())?,
+        },
+    ))
+}
+
+pub fn parse_spell_absorbed_line(input: &str) -> IResult<&str, LogSpellAbsorbed<'_>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("SPELL_ABSORBED"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    // Absorbed melee swings come without the spell. Classic logs end with the
+    // amount, retail logs add the base amount and whether the hit was a critical.
+    let with_spell = match cols.len() {
+        16..=18 => false,
+        19..=21 => true,
+        _ => {
+            return Err(RowError::field_count(
+                input,
+                &[16, 17, 18, 19, 20, 21],
+                cols.len(),
+            ))
+        }
+    };
+    let trailing = cols.len() - if with_spell { 19 } else { 16 };
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSpellAbsorbed {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            spellId: with_spell.then(|| cols_iter.next().unwrap()),
+            spellName: with_spell.then(|| cols_iter.next().unwrap()),
+            spellSchool: with_spell.then(|| cols_iter.next().unwrap().into()),
+            casterGUID: cols_iter.next().unwrap().into(),
+            casterName: cols_iter.next().unwrap(),
+            casterFlags: cols_iter.next().unwrap().into(),
+            casterRaidFlags: cols_iter.next().unwrap().into(),
+            absorbSpellId: cols_iter.next().unwrap(),
+            absorbSpellName: cols_iter.next().unwrap(),
+            absorbSpellSchool: cols_iter.next().unwrap().into(),
+            amount: cols_iter.next().unwrap(),
+            baseAmount: (trailing == 2).then(|| cols_iter.next().unwrap()),
+            critical: cols_iter.next().map(bool::from).unwrap_or(false),
+        },
+    ))
+}
+
+pub fn parse_encounter_start_line(input: &str) -> IResult<&str, LogEncounterStart<'_>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("ENCOUNTER_START"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() != 5 {
+        return Err(RowError::field_count(input, &[5], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogEncounterStart {
+            encounterID: cols_iter.next().unwrap(),
+            encounterName: cols_iter.next().unwrap(),
+            difficultyID: cols_iter.next().unwrap(),
+            groupSize: cols_iter.next().unwrap(),
+            instanceID: cols_iter.next().unwrap(),
+        },
+    ))
+}
+
+pub fn parse_encounter_end_line(input: &str) -> IResult<&str, LogEncounterEnd<'_>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("ENCOUNTER_END"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() != 6 {
+        return Err(RowError::field_count(input, &[6], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogEncounterEnd {
+            encounterID: cols_iter.next().unwrap(),
+            encounterName: cols_iter.next().unwrap(),
+            difficultyID: cols_iter.next().unwrap(),
+            groupSize: cols_iter.next().unwrap(),
+            success: cols_iter.next().unwrap().into(),
+            fightTime: cols_iter.next().unwrap(),
+        },
+    ))
+}
+
+pub fn parse_zone_change_line(input: &str) -> IResult<&str, LogZoneChange<'_>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("ZONE_CHANGE"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() != 3 {
+        return Err(RowError::field_count(input, &[3], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogZoneChange {
+            instanceID: cols_iter.next().unwrap(),
+            zoneName: cols_iter.next().unwrap(),
+            difficultyID: cols_iter.next().unwrap(),
+        },
+    ))
+}
+
+pub fn parse_spell_summon_line<'a>(
+    event_name: &'static str,
+    input: &'a str,
+) -> IResult<&'a str, LogSpellSummon<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag(event_name),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() != 11 {
+        return Err(RowError::field_count(input, &[11], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogSpellSummon {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            spellId: cols_iter.next().unwrap(),
+            spellName: cols_iter.next().unwrap(),
+            spellSchool: cols_iter.next().unwrap().into(),
+        },
+    ))
+}
+
+pub fn parse_unit_died_line<'a>(
+    event_name: &'static str,
+    input: &'a str,
+) -> IResult<&'a str, LogUnitDied<'a>> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag(event_name),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() != 8 && cols.len() != 9 {
+        return Err(RowError::field_count(input, &[8, 9], cols.len()));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        LogUnitDied {
+            sourceGUID: cols_iter.next().unwrap().into(),
+            sourceName: cols_iter.next().unwrap(),
+            sourceFlags: cols_iter.next().unwrap().into(),
+            sourceRaidFlags: cols_iter.next().unwrap().into(),
+            destGUID: cols_iter.next().unwrap().into(),
+            destName: cols_iter.next().unwrap(),
+            destFlags: cols_iter.next().unwrap().into(),
+            destRaidFlags: cols_iter.next().unwrap().into(),
+            unconsciousOnDeath: cols_iter.next().map(bool::from).unwrap_or(false),
         },
     ))
 }
@@ -542,6 +1688,179 @@ mod tests {
     #[test]
     fn parse_spell_damage_event() {
         let input = "SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2515-19964-196102-000550239A,0000000000000000,1483954,1952835,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,488,488,-1,8,0,0,0,nil,nil,nil";
-        parse_spell_damage_line(input).unwrap();
+        let (_, damage) = parse_spell_damage_line(input, &LogLayout::default()).unwrap();
+        assert_eq!(damage.spellSchool, SpellSchool::NATURE);
+        assert_eq!(damage.school, SpellSchool::NATURE);
+    }
+
+    #[test]
+    fn parse_lines_per_layout() {
+        let input = "COMBAT_LOG_VERSION,9,ADVANCED_LOG_ENABLED,0,BUILD_VERSION,3.4.3,PROJECT_ID,11";
+        let (_, header) = parse_combat_log_version_line(input).unwrap();
+        assert_eq!(header.buildVersion, "3.4.3");
+        let classic = LogLayout::from(&header);
+        assert!(!classic.advanced);
+
+        let input = "SPELL_DAMAGE,Player-4395-0390A6B5,\"Yerrog-Whitemane\",0x512,0x0,Creature-0-4395-615-2719-28860-00004F3A5B,\"Sartharion\",0x10a48,0x0,47465,\"Rend\",0x1,2215,-1,1,0,0,0,1,nil,nil,1";
+        let (_, damage) = parse_spell_damage_line(input, &classic).unwrap();
+        assert_eq!(damage.advanced, None);
+        assert_eq!(damage.amount, LogCell::Integer(2215));
+        assert_eq!(damage.baseAmount, None);
+        assert!(damage.critical);
+        assert!(damage.isOffHand);
+
+        // Partial misses carry no unmitigated amount before version 20.
+        let input = "SWING_MISSED,Creature-0-4395-615-2719-28860-00004F3A5B,\"Sartharion\",0x10a48,0x0,Player-4395-0390A6B5,\"Yerrog-Whitemane\",0x512,0x0,ABSORB,nil,1250,1";
+        let (_, missed) = parse_swing_missed_line(input, &classic).unwrap();
+        assert_eq!(missed.amountMissed, Some(LogCell::Integer(1250)));
+        assert!(missed.critical);
+        assert!(parse_swing_missed_line(input, &LogLayout::default()).is_err());
+
+        let input = "SPELL_MISSED,Creature-0-4395-615-2719-28860-00004F3A5B,\"Sartharion\",0x10a48,0x0,Player-4395-0390A6B5,\"Yerrog-Whitemane\",0x512,0x0,57491,\"Flame Tsunami\",0x4,RESIST,nil,3100,nil";
+        let (_, missed) = parse_spell_missed_line("SPELL_MISSED", input, &classic).unwrap();
+        assert_eq!(missed.missType.as_str(), Some("RESIST"));
+        assert_eq!(missed.amountMissed, Some(LogCell::Integer(3100)));
+        assert!(!missed.critical);
+
+        let input = "SPELL_MISSED,Creature-0-4395-615-2719-28860-00004F3A5B,\"Sartharion\",0x10a48,0x0,Player-4395-0390A6B5,\"Yerrog-Whitemane\",0x512,0x0,57491,\"Flame Tsunami\",0x4,DODGE,nil";
+        let (_, missed) = parse_spell_missed_line("SPELL_MISSED", input, &classic).unwrap();
+        assert_eq!(missed.amountMissed, None);
+
+        let input = "SPELL_HEAL,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,22842,\"Frenzied Regeneration\",0x1,Player-1379-0A9FF58F,0000000000000000,1483954,1952835,9102,1200,5043,0,1,60,100,0,-5095.52,1142.47,2073,6.1556,70,31204,40117,8913,0,nil";
+        let (_, heal) = parse_spell_heal_line(input, &LogLayout::default()).unwrap();
+        assert_eq!(heal.amount, LogCell::Integer(31204));
+        assert_eq!(heal.overhealing, LogCell::Integer(8913));
+        assert!(!heal.critical);
+
+        let input = "ZONE_CHANGE,2569,\"Aberrus, the Shadowed Crucible\",16";
+        let (_, zone) = parse_zone_change_line(input).unwrap();
+        assert_eq!(zone.instanceID, LogCell::Integer(2569));
+        assert_eq!(
+            zone.zoneName.as_str(),
+            Some("Aberrus, the Shadowed Crucible")
+        );
+    }
+
+    #[test]
+    fn parse_spell_absorbed_events() {
+        let input = "SPELL_ABSORBED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,405316,\"Ancient Fury\",0x4,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,17,\"Power Word: Shield\",0x2,52345,80000,1";
+        let (_, absorbed) = parse_spell_absorbed_line(input).unwrap();
+        assert_eq!(absorbed.spellId, Some(LogCell::Integer(405316)));
+        assert_eq!(absorbed.spellSchool, Some(SpellSchool::FIRE));
+        assert_eq!(absorbed.casterName.as_str(), Some("Priest-Sanguino"));
+        assert_eq!(absorbed.absorbSpellId, LogCell::Integer(17));
+        assert_eq!(absorbed.amount, LogCell::Integer(52345));
+        assert_eq!(absorbed.baseAmount, Some(LogCell::Integer(80000)));
+        assert!(absorbed.critical);
+
+        // A melee swing, without the spell.
+        let input = "SPELL_ABSORBED,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0B1C2D3E,\"Priest-Sanguino\",0x514,0x0,17,\"Power Word: Shield\",0x2,20000,25000,nil";
+        let (_, absorbed) = parse_spell_absorbed_line(input).unwrap();
+        assert_eq!(absorbed.spellId, None);
+        assert_eq!(absorbed.casterName.as_str(), Some("Priest-Sanguino"));
+        assert_eq!(absorbed.amount, LogCell::Integer(20000));
+        assert!(!absorbed.critical);
+
+        // Classic logs end with the amount.
+        let input = "SPELL_ABSORBED,Creature-0-4395-615-2719-28860-00004F3A5B,\"Sartharion\",0x10a48,0x0,Player-4395-0390A6B5,\"Yerrog-Whitemane\",0x512,0x0,Player-4395-0390A6B6,\"Priest-Whitemane\",0x514,0x0,48066,\"Power Word: Shield\",0x2,4000";
+        let (_, absorbed) = parse_spell_absorbed_line(input).unwrap();
+        assert_eq!(absorbed.amount, LogCell::Integer(4000));
+        assert_eq!(absorbed.baseAmount, None);
+    }
+
+    #[test]
+    fn parse_spell_summon_events() {
+        let input = "SPELL_SUMMON,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-103673-000550239A,\"Darkglare\",0xa28,0x0,205180,\"Summon Darkglare\",0x20";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        match row {
+            LogRow::SpellSummon(summon) => {
+                assert_eq!(summon.sourceGUID.to_string(), "Player-1379-0A9FF58F");
+                assert_eq!(summon.destGUID.npc_id(), Some(103673));
+                assert_eq!(summon.spellSchool, SpellSchool::SHADOW);
+            }
+            row => panic!("Expected a summon, got {:?}", row),
+        }
+
+        let input = "SPELL_CREATE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,GameObject-0-4252-2569-19964-187299-000550239A,\"Freezing Trap\",0xa28,0x0,187650,\"Freezing Trap\",0x10";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        assert!(matches!(row, LogRow::SpellCreate(_)));
+    }
+
+    #[test]
+    fn parse_periodic_events() {
+        let input = "SPELL_PERIODIC_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,155722,\"Rake\",0x1,Creature-0-4252-2515-19964-196102-000550239A,0000000000000000,1483954,1952835,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,1093,1093,-1,1,0,0,0,1,nil,nil";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        match row {
+            LogRow::SpellPeriodicDamage(damage) => assert!(damage.periodic),
+            row => panic!("Expected periodic damage, got {:?}", row),
+        }
+
+        let input = "RANGE_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,75,\"Auto Shot\",0x1,Creature-0-4252-2515-19964-196102-000550239A,0000000000000000,1483954,1952835,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,2093,2093,-1,1,0,0,0,1,nil,nil";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        match row {
+            LogRow::RangeDamage(damage) => {
+                assert!(!damage.periodic);
+                assert!(damage.critical);
+            }
+            row => panic!("Expected range damage, got {:?}", row),
+        }
+
+        let input = "SPELL_PERIODIC_MISSED,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,155722,\"Rake\",0x1,IMMUNE,nil";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        assert!(matches!(row, LogRow::SpellPeriodicMissed(_)));
+
+        let input = "SPELL_PERIODIC_ENERGIZE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,143625,\"Brutal Slash\",0x1,Player-1379-0A9FF58F,0000000000000000,1483954,1483954,9102,1200,5043,0,3,80,100,0,-5095.52,1142.47,2073,6.1556,70,5.0000,0.0000,3,100";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        assert!(matches!(row, LogRow::SpellPeriodicEnergize(_)));
+    }
+
+    #[test]
+    fn parse_aura_events() {
+        let input = "SPELL_AURA_APPLIED_DOSE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,391882,\"Apex Predator's Craving\",0x1,BUFF,3";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        match row {
+            LogRow::SpellAuraAppliedDose(aura) => {
+                assert_eq!(aura.auraType, AuraType::Buff);
+                assert_eq!(aura.amount, Some(LogCell::Integer(3)));
+            }
+            row => panic!("Expected aura dose, got {:?}", row),
+        }
+
+        let input = "SPELL_AURA_REMOVED,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,155722,\"Rake\",0x1,DEBUFF";
+        let (_, aura) = parse_spell_aura_line("SPELL_AURA_REMOVED", input).unwrap();
+        assert_eq!(aura.auraType, AuraType::Debuff);
+        assert_eq!(aura.amount, None);
+
+        let input = "SPELL_AURA_BROKEN_SPELL,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,339,\"Entangling Roots\",0x8,1822,\"Rake\",1,DEBUFF";
+        let (_, broken) = parse_spell_aura_broken_spell_line(input).unwrap();
+        assert_eq!(broken.extraSpellName, LogCell::Str("Rake".into()));
+        assert_eq!(broken.auraType, AuraType::Debuff);
+    }
+
+    #[test]
+    fn parse_swing_events() {
+        let input = "SWING_DAMAGE,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,0000000000000000,1483954,1952835,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,2310,3301,-1,1,0,0,0,nil,nil,nil";
+        let (_, row) = parse_log_csv(input, &LogLayout::default()).unwrap();
+        assert!(matches!(row, LogRow::SwingDamage(_)));
+        assert!(row.source_flags().unwrap().is_hostile());
+        assert!(row.dest_flags().unwrap().is_player());
+
+        let input = "SWING_MISSED,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,PARRY,nil";
+        let (_, missed) = parse_swing_missed_line(input, &LogLayout::default()).unwrap();
+        assert_eq!(missed.missType, LogCell::Str("PARRY".into()));
+        assert_eq!(missed.amountMissed, None);
+
+        let input = "SWING_MISSED,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,ABSORB,1,2310,3301,nil";
+        let (_, missed) = parse_swing_missed_line(input, &LogLayout::default()).unwrap();
+        assert!(missed.isOffHand);
+        assert_eq!(missed.amountMissed, Some(LogCell::Integer(2310)));
+        assert!(!missed.critical);
+    }
+
+    #[test]
+    fn parse_numbers_starting_with_nine() {
+        assert_eq!(parse_log_cell("9"), Ok(("", LogCell::Integer(9))));
+        assert_eq!(parse_log_cell("9102,"), Ok((",", LogCell::Integer(9102))));
+        assert_eq!(parse_log_cell("95.25]"), Ok(("]", LogCell::Float(95.25))));
     }
 }
@@ -0,0 +1,697 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use nom::error::ErrorKind;
+
+use super::cell::row_structs;
+use super::cell::*;
+use super::combatant::{CombatantInfo, Gear, Gem, InterestingAura, PvpInfo, Stats, Talent};
+use super::error::{FieldCount, ParseError, ParseErrorKind};
+use super::flags::{RaidFlags, UnitFlags};
+use super::guid::{Guid, UnitGuid};
+use super::school::SpellSchool;
+use super::time::LogEventDateTime;
+
+/// The columns of an encoded log. Every value is written to the column of its
+/// kind, so that similar values end up next to each other.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Columns<T> {
+    // Enum variants, `Option`s and `bool`s, one byte each.
+    pub tags: T,
+    // Integers, as zigzag varints.
+    pub ints: T,
+    pub floats: T,
+    // Indices into the string table, as varints.
+    pub strings: T,
+    // Indices into the GUID table, as varints.
+    pub guids: T,
+    // Unit, raid and school flags, as varints.
+    pub flags: T,
+}
+
+impl<T> Columns<T> {
+    pub fn from_each([tags, ints, floats, strings, guids, flags]: [T; 6]) -> Self {
+        Self {
+            tags,
+            ints,
+            floats,
+            strings,
+            guids,
+            flags,
+        }
+    }
+
+    pub fn each(&self) -> [&T; 6] {
+        [
+            &self.tags,
+            &self.ints,
+            &self.floats,
+            &self.strings,
+            &self.guids,
+            &self.flags,
+        ]
+    }
+
+    pub fn map<'a, U>(&'a self, f: impl Fn(&'a T) -> U) -> Columns<U> {
+        Columns::from_each(self.each().map(f))
+    }
+}
+
+pub(super) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub(super) fn read_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = input.split_first()?;
+        *input = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+// Small negative numbers, e.g. an overkill of -1, stay small.
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+pub(super) fn write_str(out: &mut Vec<u8>, value: &str) {
+    write_varint(out, value.len() as u64);
+    out.extend_from_slice(value.as_bytes());
+}
+
+pub(super) fn read_str(input: &mut &[u8]) -> Option<String> {
+    let len = usize::try_from(read_varint(input)?).ok()?;
+    if input.len() < len {
+        return None;
+    }
+    let (value, rest) = input.split_at(len);
+    *input = rest;
+
+    String::from_utf8(value.to_vec()).ok()
+}
+
+/// Encodes values into `Columns`, interning strings and GUIDs.
+#[derive(Default)]
+pub(super) struct Encoder {
+    pub columns: Columns<Vec<u8>>,
+    pub strings: Vec<String>,
+    string_ids: HashMap<String, u64>,
+    pub guids: Vec<Guid>,
+    guid_ids: HashMap<Guid, u64>,
+}
+
+impl Encoder {
+    pub fn tag(&mut self, tag: u8) {
+        self.columns.tags.push(tag);
+    }
+
+    pub fn int(&mut self, value: i64) {
+        write_varint(&mut self.columns.ints, zigzag(value));
+    }
+
+    fn float(&mut self, value: f64) {
+        self.columns.floats.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        let id = match self.string_ids.get(value) {
+            Some(id) => *id,
+            None => {
+                let id = self.strings.len() as u64;
+                self.strings.push(value.to_string());
+                self.string_ids.insert(value.to_string(), id);
+                id
+            }
+        };
+        write_varint(&mut self.columns.strings, id);
+    }
+
+    fn guid(&mut self, guid: &Guid) {
+        let guids = &mut self.guids;
+        let id = *self.guid_ids.entry(guid.clone()).or_insert_with(|| {
+            guids.push(guid.clone());
+            guids.len() as u64 - 1
+        });
+        write_varint(&mut self.columns.guids, id);
+    }
+
+    fn flags(&mut self, bits: u32) {
+        write_varint(&mut self.columns.flags, bits.into());
+    }
+}
+
+/// Decodes values from `Columns`, in the order they were encoded in.
+pub(super) struct Decoder<'a> {
+    pub columns: Columns<&'a [u8]>,
+    pub strings: &'a [String],
+    pub guids: &'a [Guid],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn tag(&mut self) -> Option<u8> {
+        let (tag, rest) = self.columns.tags.split_first()?;
+        self.columns.tags = rest;
+        Some(*tag)
+    }
+
+    pub fn int(&mut self) -> Option<i64> {
+        read_varint(&mut self.columns.ints).map(unzigzag)
+    }
+
+    fn float(&mut self) -> Option<f64> {
+        let (bytes, rest) = self.columns.floats.split_first_chunk::<8>()?;
+        self.columns.floats = rest;
+        Some(f64::from_le_bytes(*bytes))
+    }
+
+    fn string(&mut self) -> Option<&'a str> {
+        let id = read_varint(&mut self.columns.strings)?;
+        self.strings
+            .get(usize::try_from(id).ok()?)
+            .map(String::as_str)
+    }
+
+    fn guid(&mut self) -> Option<Guid> {
+        let id = read_varint(&mut self.columns.guids)?;
+        self.guids.get(usize::try_from(id).ok()?).cloned()
+    }
+
+    fn flags(&mut self) -> Option<u32> {
+        u32::try_from(read_varint(&mut self.columns.flags)?).ok()
+    }
+}
+
+/// A value that can be written to and read back from `Columns`.
+pub(super) trait Columnar<'a>: Sized {
+    fn encode(&self, out: &mut Encoder);
+
+    /// `None` if the columns do not hold a valid value.
+    fn decode(input: &mut Decoder<'a>) -> Option<Self>;
+}
+
+impl<'a> Columnar<'a> for bool {
+    fn encode(&self, out: &mut Encoder) {
+        out.tag(*self as u8);
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        match input.tag()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Columnar<'a> for i64 {
+    fn encode(&self, out: &mut Encoder) {
+        out.int(*self);
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.int()
+    }
+}
+
+impl<'a> Columnar<'a> for u32 {
+    fn encode(&self, out: &mut Encoder) {
+        out.int((*self).into());
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.int()?.try_into().ok()
+    }
+}
+
+impl<'a> Columnar<'a> for usize {
+    fn encode(&self, out: &mut Encoder) {
+        out.int(*self as i64);
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.int()?.try_into().ok()
+    }
+}
+
+impl<'a> Columnar<'a> for Cow<'a, str> {
+    fn encode(&self, out: &mut Encoder) {
+        out.string(self);
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.string().map(Cow::Borrowed)
+    }
+}
+
+impl<'a> Columnar<'a> for String {
+    fn encode(&self, out: &mut Encoder) {
+        out.string(self);
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.string().map(str::to_string)
+    }
+}
+
+impl<'a> Columnar<'a> for Guid {
+    fn encode(&self, out: &mut Encoder) {
+        out.guid(self);
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.guid()
+    }
+}
+
+impl<'a> Columnar<'a> for UnitFlags {
+    fn encode(&self, out: &mut Encoder) {
+        out.flags(self.bits());
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.flags().map(UnitFlags::from_bits_retain)
+    }
+}
+
+impl<'a> Columnar<'a> for RaidFlags {
+    fn encode(&self, out: &mut Encoder) {
+        out.flags(self.bits());
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        input.flags().map(RaidFlags::from_bits_retain)
+    }
+}
+
+impl<'a> Columnar<'a> for SpellSchool {
+    fn encode(&self, out: &mut Encoder) {
+        out.flags(self.bits().into());
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        let bits = u8::try_from(input.flags()?).ok()?;
+        Some(SpellSchool::from_bits_retain(bits))
+    }
+}
+
+impl<'a> Columnar<'a> for AuraType {
+    fn encode(&self, out: &mut Encoder) {
+        out.tag(match self {
+            AuraType::Buff => 0,
+            AuraType::Debuff => 1,
+        });
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        match input.tag()? {
+            0 => Some(AuraType::Buff),
+            1 => Some(AuraType::Debuff),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, T: Columnar<'a>> Columnar<'a> for Option<T> {
+    fn encode(&self, out: &mut Encoder) {
+        match self {
+            Some(value) => {
+                out.tag(1);
+                value.encode(out);
+            }
+            None => out.tag(0),
+        }
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        match input.tag()? {
+            0 => Some(None),
+            1 => T::decode(input).map(Some),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, T: Columnar<'a>> Columnar<'a> for Vec<T> {
+    fn encode(&self, out: &mut Encoder) {
+        self.len().encode(out);
+        for value in self {
+            value.encode(out);
+        }
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        let len = usize::decode(input)?;
+        // The length is not trusted with an allocation before the values are read.
+        let mut values = Vec::new();
+        for _ in 0..len {
+            values.push(T::decode(input)?);
+        }
+
+        Some(values)
+    }
+}
+
+impl<'a> Columnar<'a> for LogCell<'a> {
+    fn encode(&self, out: &mut Encoder) {
+        match self {
+            LogCell::Integer(v) => {
+                out.tag(0);
+                out.int(*v);
+            }
+            LogCell::Float(v) => {
+                out.tag(1);
+                out.float(*v);
+            }
+            LogCell::MultiPowerCell((a, b)) => {
+                out.tag(2);
+                out.int(*a);
+                out.int(*b);
+            }
+            LogCell::Str(v) => {
+                out.tag(3);
+                v.encode(out);
+            }
+            LogCell::Array(v) => {
+                out.tag(4);
+                v.encode(out);
+            }
+        }
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        Some(match input.tag()? {
+            0 => LogCell::Integer(input.int()?),
+            1 => LogCell::Float(input.float()?),
+            2 => LogCell::MultiPowerCell((input.int()?, input.int()?)),
+            3 => LogCell::Str(Columnar::decode(input)?),
+            4 => LogCell::Array(Columnar::decode(input)?),
+            _ => return None,
+        })
+    }
+}
+
+// Every field is written in declaration order, and read back in the same order.
+macro_rules! impl_columnar {
+    ($($name:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl<'a> Columnar<'a> for $name<'a> {
+                fn encode(&self, out: &mut Encoder) {
+                    $(self.$field.encode(out);)*
+                }
+
+                fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+                    Some($name {
+                        $($field: Columnar::decode(input)?),*
+                    })
+                }
+            }
+        )*
+    };
+}
+
+// Like `impl_columnar`, for the structs that do not borrow from the line.
+macro_rules! impl_columnar_owned {
+    ($($name:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl<'a> Columnar<'a> for $name {
+                fn encode(&self, out: &mut Encoder) {
+                    $(self.$field.encode(out);)*
+                }
+
+                fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+                    Some($name {
+                        $($field: Columnar::decode(input)?),*
+                    })
+                }
+            }
+        )*
+    };
+}
+
+row_structs!(impl_columnar);
+
+impl_columnar_owned! {
+    CombatantInfo {
+        playerGUID, faction, stats, specId, talents, pvpTalents, gear, interestingAuras, pvp,
+    }
+    Stats {
+        strength, agility, stamina, intelligence, dodge, parry, block,
+        critMelee, critRanged, critSpell, speed, lifesteal, hasteMelee, hasteRanged, hasteSpell,
+        avoidance, mastery, versatilityDamageDone, versatilityHealingDone, versatilityDamageTaken,
+        armor,
+    }
+    Talent { nodeId, entryId, rank }
+    Gear { itemId, ilvl, enchantIds, bonusIds, gems }
+    Gem { itemId, ilvl }
+    InterestingAura { casterGUID, spellId }
+    PvpInfo { honorLevel, season, rating, tier }
+    LogEventDateTime { year, month, day, hour, minute, second, ms, utc_offset }
+    FieldCount { expected, actual }
+    ParseError { line, offset, event, kind }
+}
+
+// The tags are part of the cache format, so a variant keeps its tag forever.
+macro_rules! impl_columnar_rows {
+    ($($tag:literal => $variant:ident),* $(,)?) => {
+        impl<'a> Columnar<'a> for LogRow<'a> {
+            fn encode(&self, out: &mut Encoder) {
+                match self {
+                    $(
+                        LogRow::$variant(row) => {
+                            out.tag($tag);
+                            row.encode(out);
+                        }
+                    )*
+                    LogRow::NotSupported => out.tag(u8::MAX),
+                }
+            }
+
+            fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+                Some(match input.tag()? {
+                    $($tag => LogRow::$variant(Columnar::decode(input)?),)*
+                    u8::MAX => LogRow::NotSupported,
+                    _ => return None,
+                })
+            }
+        }
+    };
+}
+
+impl_columnar_rows! {
+    0 => Emote,
+    1 => SpellCastSuccess,
+    2 => SpellDamage,
+    3 => SpellHeal,
+    4 => SpellMissed,
+    5 => SpellEnergize,
+    6 => SpellDrain,
+    7 => SpellLeech,
+    8 => SpellPeriodicDamage,
+    9 => SpellPeriodicHeal,
+    10 => SpellPeriodicMissed,
+    11 => SpellPeriodicEnergize,
+    12 => SpellPeriodicDrain,
+    13 => SpellPeriodicLeech,
+    14 => SpellAuraApplied,
+    15 => SpellAuraRemoved,
+    16 => SpellAuraRefresh,
+    17 => SpellAuraAppliedDose,
+    18 => SpellAuraRemovedDose,
+    19 => SpellAuraBroken,
+    20 => SpellAuraBrokenSpell,
+    21 => CombatLogVersion,
+    22 => CombatantInfo,
+    23 => UnitDied,
+    24 => UnitDestroyed,
+    25 => UnitDissipates,
+    26 => PartyKill,
+    27 => EncounterStart,
+    28 => EncounterEnd,
+    29 => SwingDamage,
+    30 => SwingMissed,
+    31 => ZoneChange,
+    32 => RangeDamage,
+    33 => SpellAbsorbed,
+    34 => SpellSummon,
+    35 => SpellCreate,
+}
+
+impl<'a> Columnar<'a> for ParseErrorKind {
+    fn encode(&self, out: &mut Encoder) {
+        match self {
+            ParseErrorKind::FieldCount(field_count) => {
+                out.tag(0);
+                field_count.encode(out);
+            }
+            ParseErrorKind::Syntax(code) => {
+                out.tag(1);
+                let index = ERROR_KINDS.iter().position(|kind| kind == code);
+                index.unwrap_or_default().encode(out);
+            }
+            ParseErrorKind::TrailingData => out.tag(2),
+            ParseErrorKind::InvalidUtf8 => out.tag(3),
+            ParseErrorKind::Io(message) => {
+                out.tag(4);
+                message.encode(out);
+            }
+        }
+    }
+
+    fn decode(input: &mut Decoder<'a>) -> Option<Self> {
+        Some(match input.tag()? {
+            0 => ParseErrorKind::FieldCount(Columnar::decode(input)?),
+            1 => ParseErrorKind::Syntax(*ERROR_KINDS.get(usize::decode(input)?)?),
+            2 => ParseErrorKind::TrailingData,
+            3 => ParseErrorKind::InvalidUtf8,
+            4 => ParseErrorKind::Io(Columnar::decode(input)?),
+            _ => return None,
+        })
+    }
+}
+
+// nom has no conversion from a number back to an `ErrorKind`, so the kinds are
+// stored as their index in this list. New kinds go at the end.
+const ERROR_KINDS: [ErrorKind; 53] = [
+    ErrorKind::Tag,
+    ErrorKind::MapRes,
+    ErrorKind::MapOpt,
+    ErrorKind::Alt,
+    ErrorKind::IsNot,
+    ErrorKind::IsA,
+    ErrorKind::SeparatedList,
+    ErrorKind::SeparatedNonEmptyList,
+    ErrorKind::Many0,
+    ErrorKind::Many1,
+    ErrorKind::ManyTill,
+    ErrorKind::Count,
+    ErrorKind::TakeUntil,
+    ErrorKind::LengthValue,
+    ErrorKind::TagClosure,
+    ErrorKind::Alpha,
+    ErrorKind::Digit,
+    ErrorKind::HexDigit,
+    ErrorKind::OctDigit,
+    ErrorKind::AlphaNumeric,
+    ErrorKind::Space,
+    ErrorKind::MultiSpace,
+    ErrorKind::LengthValueFn,
+    ErrorKind::Eof,
+    ErrorKind::Switch,
+    ErrorKind::TagBits,
+    ErrorKind::OneOf,
+    ErrorKind::NoneOf,
+    ErrorKind::Char,
+    ErrorKind::CrLf,
+    ErrorKind::RegexpMatch,
+    ErrorKind::RegexpMatches,
+    ErrorKind::RegexpFind,
+    ErrorKind::RegexpCapture,
+    ErrorKind::RegexpCaptures,
+    ErrorKind::TakeWhile1,
+    ErrorKind::Complete,
+    ErrorKind::Fix,
+    ErrorKind::Escaped,
+    ErrorKind::EscapedTransform,
+    ErrorKind::NonEmpty,
+    ErrorKind::ManyMN,
+    ErrorKind::Not,
+    ErrorKind::Permutation,
+    ErrorKind::Verify,
+    ErrorKind::TakeTill1,
+    ErrorKind::TakeWhileMN,
+    ErrorKind::TooLarge,
+    ErrorKind::Many0Count,
+    ErrorKind::Many1Count,
+    ErrorKind::Float,
+    ErrorKind::Satisfy,
+    ErrorKind::Fail,
+];
+
+/// Writes a GUID to the GUID table.
+pub(super) fn write_guid(out: &mut Vec<u8>, guid: &Guid) {
+    let write_unit = |out: &mut Vec<u8>, tag: u8, unit: &UnitGuid| {
+        out.push(tag);
+        write_varint(out, unit.server_id.into());
+        write_varint(out, unit.instance_id.into());
+        write_varint(out, unit.zone_uid.into());
+        write_varint(out, unit.npc_id.into());
+        write_varint(out, unit.spawn_uid);
+    };
+
+    match guid {
+        Guid::Player {
+            server_id,
+            player_uid,
+        } => {
+            out.push(0);
+            write_varint(out, (*server_id).into());
+            write_varint(out, (*player_uid).into());
+        }
+        Guid::Creature(unit) => write_unit(out, 1, unit),
+        Guid::Pet(unit) => write_unit(out, 2, unit),
+        Guid::Vehicle(unit) => write_unit(out, 3, unit),
+        Guid::GameObject(unit) => write_unit(out, 4, unit),
+        Guid::Item {
+            server_id,
+            spawn_uid,
+        } => {
+            out.push(5);
+            write_varint(out, (*server_id).into());
+            write_varint(out, *spawn_uid);
+        }
+        Guid::Vignette(unit) => write_unit(out, 6, unit),
+        Guid::Nil => out.push(7),
+        Guid::Unknown(text) => {
+            out.push(8);
+            write_str(out, text);
+        }
+    }
+}
+
+pub(super) fn read_guid(input: &mut &[u8]) -> Option<Guid> {
+    let read_u32 = |input: &mut &[u8]| u32::try_from(read_varint(input)?).ok();
+    let read_unit = |input: &mut &[u8]| {
+        Some(UnitGuid {
+            server_id: read_u32(input)?,
+            instance_id: read_u32(input)?,
+            zone_uid: read_u32(input)?,
+            npc_id: read_u32(input)?,
+            spawn_uid: read_varint(input)?,
+        })
+    };
+
+    let (tag, rest) = input.split_first()?;
+    *input = rest;
+    Some(match tag {
+        0 => Guid::Player {
+            server_id: read_u32(input)?,
+            player_uid: read_u32(input)?,
+        },
+        1 => Guid::Creature(read_unit(input)?),
+        2 => Guid::Pet(read_unit(input)?),
+        3 => Guid::Vehicle(read_unit(input)?),
+        4 => Guid::GameObject(read_unit(input)?),
+        5 => Guid::Item {
+            server_id: read_u32(input)?,
+            spawn_uid: read_varint(input)?,
+        },
+        6 => Guid::Vignette(read_unit(input)?),
+        7 => Guid::Nil,
+        8 => Guid::Unknown(read_str(input)?.into()),
+        _ => return None,
+    })
+}
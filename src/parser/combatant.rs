@@ -0,0 +1,290 @@
+use nom::{bytes::complete::tag, multi::separated_list1, sequence::tuple};
+
+use super::cell::{parse_log_cell, LogCell};
+use super::error::{IResult, RowError};
+use super::guid::Guid;
+
+/// A raider's loadout, written by COMBATANT_INFO at the start of every encounter.
+#[derive(Debug, PartialEq)]
+pub struct CombatantInfo {
+    pub playerGUID: Guid,
+    // 0 for Horde, 1 for Alliance.
+    pub faction: i64,
+    pub stats: Stats,
+    pub specId: i64,
+    pub talents: Vec<Talent>,
+    pub pvpTalents: Vec<i64>,
+    pub gear: Vec<Gear>,
+    pub interestingAuras: Vec<InterestingAura>,
+    pub pvp: PvpInfo,
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct Stats {
+    pub strength: i64,
+    pub agility: i64,
+    pub stamina: i64,
+    pub intelligence: i64,
+    pub dodge: i64,
+    pub parry: i64,
+    pub block: i64,
+    pub critMelee: i64,
+    pub critRanged: i64,
+    pub critSpell: i64,
+    pub speed: i64,
+    pub lifesteal: i64,
+    pub hasteMelee: i64,
+    pub hasteRanged: i64,
+    pub hasteSpell: i64,
+    pub avoidance: i64,
+    pub mastery: i64,
+    pub versatilityDamageDone: i64,
+    pub versatilityHealingDone: i64,
+    pub versatilityDamageTaken: i64,
+    pub armor: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Talent {
+    pub nodeId: i64,
+    pub entryId: i64,
+    pub rank: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Gear {
+    // Empty slots are written with item id 0.
+    pub itemId: i64,
+    pub ilvl: i64,
+    // Permanent, temporary and on-use enchants, without the empty slots.
+    pub enchantIds: Vec<i64>,
+    pub bonusIds: Vec<i64>,
+    pub gems: Vec<Gem>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Gem {
+    pub itemId: i64,
+    pub ilvl: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InterestingAura {
+    pub casterGUID: Guid,
+    pub spellId: i64,
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct PvpInfo {
+    pub honorLevel: i64,
+    pub season: i64,
+    pub rating: i64,
+    pub tier: i64,
+}
+
+fn int(cell: LogCell) -> i64 {
+    cell.as_i64().unwrap_or_default()
+}
+
+fn array(cell: LogCell) -> Vec<LogCell> {
+    match cell {
+        LogCell::Array(v) => v,
+        _ => Vec::new(),
+    }
+}
+
+fn parse_talent(cell: LogCell) -> Talent {
+    match cell {
+        // Dragonflight talent trees: (nodeId, entryId, rank).
+        LogCell::Array(v) => {
+            let mut v = v.into_iter();
+            Talent {
+                nodeId: v.next().map(int).unwrap_or_default(),
+                entryId: v.next().map(int).unwrap_or_default(),
+                rank: v.next().map(int).unwrap_or_default(),
+            }
+        }
+        // Older clients only write the talent id.
+        cell => Talent {
+            nodeId: 0,
+            entryId: int(cell),
+            rank: 1,
+        },
+    }
+}
+
+fn parse_gear(cell: LogCell) -> Gear {
+    let mut item = array(cell).into_iter();
+
+    Gear {
+        itemId: item.next().map(int).unwrap_or_default(),
+        ilvl: item.next().map(int).unwrap_or_default(),
+        enchantIds: item
+            .next()
+            .map(array)
+            .unwrap_or_default()
+            .into_iter()
+            .map(int)
+            .filter(|id| *id != 0)
+            .collect(),
+        bonusIds: item
+            .next()
+            .map(array)
+            .unwrap_or_default()
+            .into_iter()
+            .map(int)
+            .collect(),
+        gems: item
+            .next()
+            .map(array)
+            .unwrap_or_default()
+            .chunks(2)
+            .map(|gem| Gem {
+                itemId: gem[0].as_i64().unwrap_or_default(),
+                ilvl: gem.get(1).and_then(LogCell::as_i64).unwrap_or_default(),
+            })
+            .collect(),
+    }
+}
+
+fn parse_interesting_auras(cell: LogCell) -> Vec<InterestingAura> {
+    let mut auras = Vec::new();
+    let mut cells = array(cell).into_iter();
+
+    // Written as a flat list of caster and spell id pairs.
+    while let (Some(casterGUID), Some(spellId)) = (cells.next(), cells.next()) {
+        auras.push(InterestingAura {
+            casterGUID: casterGUID.into(),
+            spellId: int(spellId),
+        });
+    }
+
+    auras
+}
+
+// Everything up to the interesting auras. Older and Classic logs leave out the
+// PvP fields, newer ones may append fields we don't read.
+const MIN_COMBATANT_INFO_FIELDS: usize = 28;
+
+pub fn parse_combatant_info_line(input: &str) -> IResult<&str, CombatantInfo> {
+    let (remainder, (_, _, cols)) = tuple((
+        tag("COMBATANT_INFO"),
+        tag(","),
+        separated_list1(tag(","), parse_log_cell),
+    ))(input)?;
+
+    if cols.len() < MIN_COMBATANT_INFO_FIELDS {
+        return Err(RowError::field_count(
+            input,
+            &[MIN_COMBATANT_INFO_FIELDS],
+            cols.len(),
+        ));
+    }
+
+    let mut cols_iter = cols.into_iter();
+
+    Ok((
+        remainder,
+        CombatantInfo {
+            playerGUID: cols_iter.next().unwrap().into(),
+            faction: int(cols_iter.next().unwrap()),
+            stats: Stats {
+                strength: int(cols_iter.next().unwrap()),
+                agility: int(cols_iter.next().unwrap()),
+                stamina: int(cols_iter.next().unwrap()),
+                intelligence: int(cols_iter.next().unwrap()),
+                dodge: int(cols_iter.next().unwrap()),
+                parry: int(cols_iter.next().unwrap()),
+                block: int(cols_iter.next().unwrap()),
+                critMelee: int(cols_iter.next().unwrap()),
+                critRanged: int(cols_iter.next().unwrap()),
+                critSpell: int(cols_iter.next().unwrap()),
+                speed: int(cols_iter.next().unwrap()),
+                lifesteal: int(cols_iter.next().unwrap()),
+                hasteMelee: int(cols_iter.next().unwrap()),
+                hasteRanged: int(cols_iter.next().unwrap()),
+                hasteSpell: int(cols_iter.next().unwrap()),
+                avoidance: int(cols_iter.next().unwrap()),
+                mastery: int(cols_iter.next().unwrap()),
+                versatilityDamageDone: int(cols_iter.next().unwrap()),
+                versatilityHealingDone: int(cols_iter.next().unwrap()),
+                versatilityDamageTaken: int(cols_iter.next().unwrap()),
+                armor: int(cols_iter.next().unwrap()),
+            },
+            specId: int(cols_iter.next().unwrap()),
+            talents: array(cols_iter.next().unwrap())
+                .into_iter()
+                .map(parse_talent)
+                .collect(),
+            pvpTalents: array(cols_iter.next().unwrap())
+                .into_iter()
+                .map(int)
+                .collect(),
+            gear: array(cols_iter.next().unwrap())
+                .into_iter()
+                .map(parse_gear)
+                .collect(),
+            interestingAuras: parse_interesting_auras(cols_iter.next().unwrap()),
+            pvp: PvpInfo {
+                honorLevel: cols_iter.next().map(int).unwrap_or_default(),
+                season: cols_iter.next().map(int).unwrap_or_default(),
+                rating: cols_iter.next().map(int).unwrap_or_default(),
+                tier: cols_iter.next().map(int).unwrap_or_default(),
+            },
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_combatant_info_event() {
+        let input = "COMBATANT_INFO,Player-1379-0A9FF58F,1,1580,9210,41265,1590,0,0,0,1874,1874,1874,0,0,1120,1120,1120,0,2290,1030,1030,1030,6843,104,[(82210,102338,1),(82213,102341,2)],(0,0,0,0),[(202518,441,(),(6652,9230,9382,1498,8767),(192945,415)),(0,0,(),(),()),(204704,447,(6643,0,0),(6652,7980,1507,8767),())],[Player-1379-0A9FF58F,1126,Player-1379-0B0D2C10,381748],0,0,0,0";
+        let (remainder, info) = parse_combatant_info_line(input).unwrap();
+
+        assert_eq!(remainder, "");
+        assert_eq!(info.faction, 1);
+        assert_eq!(info.stats.agility, 9210);
+        assert_eq!(info.stats.armor, 6843);
+        assert_eq!(info.specId, 104);
+        assert_eq!(
+            info.talents[1],
+            Talent {
+                nodeId: 82213,
+                entryId: 102341,
+                rank: 2
+            }
+        );
+        assert_eq!(info.gear.len(), 3);
+        assert_eq!(info.gear[0].bonusIds, vec![6652, 9230, 9382, 1498, 8767]);
+        assert_eq!(
+            info.gear[0].gems,
+            vec![Gem {
+                itemId: 192945,
+                ilvl: 415
+            }]
+        );
+        assert_eq!(info.gear[1].itemId, 0);
+        assert_eq!(info.gear[2].enchantIds, vec![6643]);
+        assert_eq!(info.interestingAuras.len(), 2);
+        assert_eq!(info.interestingAuras[1].spellId, 381748);
+        assert_eq!(info.pvp, PvpInfo::default());
+
+        // Classic logs end with the interesting auras and write talent ids only.
+        let input = "COMBATANT_INFO,Player-4395-0390A6B5,0,120,1043,1597,98,0,0,0,712,712,712,0,0,301,301,301,0,0,0,0,0,5432,0,[16814,16815],(),[(40610,213,(),(),())],[Player-4395-0390A6B5,48162]";
+        let (_, info) = parse_combatant_info_line(input).unwrap();
+        assert_eq!(info.stats.agility, 1043);
+        assert_eq!(info.talents[1].entryId, 16815);
+        assert_eq!(info.gear[0].itemId, 40610);
+        assert_eq!(info.interestingAuras[0].spellId, 48162);
+        assert_eq!(info.pvp, PvpInfo::default());
+
+        // Fields added by later versions are ignored.
+        let input = "COMBATANT_INFO,Player-1379-0A9FF58F,1,1580,9210,41265,1590,0,0,0,1874,1874,1874,0,0,1120,1120,1120,0,2290,1030,1030,1030,6843,104,[],(0,0,0,0),[],[],12,0,1800,3,1";
+        let (remainder, info) = parse_combatant_info_line(input).unwrap();
+        assert_eq!(remainder, "");
+        assert_eq!(info.pvp.rating, 1800);
+    }
+}
@@ -0,0 +1,237 @@
+use std::ops::Range;
+
+use super::cell::{LogAdvancedParams, LogEncounterEnd, LogEncounterStart, LogRow};
+use super::flags::UnitFlags;
+use super::guid::Guid;
+use super::layout::LogLayout;
+
+/// The health of the creature with the most health seen in a pull, which is the
+/// boss in all but a few encounters.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BossHealth {
+    pub guid: Guid,
+    pub current: i64,
+    pub max: i64,
+}
+
+impl BossHealth {
+    /// The health that was left, in percent.
+    pub fn percent(&self) -> f64 {
+        self.current as f64 * 100.0 / self.max.max(1) as f64
+    }
+}
+
+/// A single boss pull, delimited by ENCOUNTER_START and ENCOUNTER_END.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Encounter {
+    pub id: i64,
+    pub name: String,
+    pub difficulty_id: i64,
+    pub group_size: i64,
+    pub instance_id: i64,
+    pub success: bool,
+    // Duration of the pull in milliseconds, as reported by ENCOUNTER_END.
+    pub fight_time: i64,
+    // Milliseconds since the start of the log of the first and last row of the pull.
+    pub start_time: i64,
+    pub end_time: i64,
+    // Indices of the rows belonging to this pull, including the start and end rows.
+    pub rows: Range<usize>,
+    // Byte offset of the first row in the log, and the layout in effect there, so
+    // that the pull can be parsed again without the rows before it.
+    pub offset: usize,
+    pub layout: LogLayout,
+    // The boss's health at the last row that recorded it, only known with advanced logging.
+    pub boss_health: Option<BossHealth>,
+}
+
+impl Encounter {
+    fn start(
+        index: usize,
+        offset: usize,
+        timestamp: i64,
+        layout: LogLayout,
+        start: &LogEncounterStart,
+    ) -> Self {
+        Self {
+            id: start.encounterID.as_i64().unwrap_or_default(),
+            name: start.encounterName.as_str().unwrap_or_default().to_string(),
+            difficulty_id: start.difficultyID.as_i64().unwrap_or_default(),
+            group_size: start.groupSize.as_i64().unwrap_or_default(),
+            instance_id: start.instanceID.as_i64().unwrap_or_default(),
+            success: false,
+            fight_time: 0,
+            start_time: timestamp,
+            end_time: timestamp,
+            rows: index..index + 1,
+            offset,
+            layout,
+            boss_health: None,
+        }
+    }
+
+    // Only hostile creatures that nobody controls can be the boss.
+    fn track_health(&mut self, unit: &LogAdvancedParams, flags: UnitFlags) {
+        let (Guid::Creature(_), Guid::Nil, Some(current), Some(max)) = (
+            &unit.unitGUID,
+            &unit.ownerGUID,
+            unit.currHp.as_i64(),
+            unit.maxHp.as_i64(),
+        ) else {
+            return;
+        };
+        if !flags.is_hostile() {
+            return;
+        }
+        let is_boss = self
+            .boss_health
+            .as_ref()
+            .is_none_or(|boss| boss.guid == unit.unitGUID || boss.max < max);
+        if is_boss {
+            self.boss_health = Some(BossHealth {
+                guid: unit.unitGUID.clone(),
+                current,
+                max,
+            });
+        }
+    }
+
+    fn end(&mut self, index: usize, timestamp: i64, end: &LogEncounterEnd) {
+        self.success = end.success;
+        self.fight_time = end.fightTime.as_i64().unwrap_or_default();
+        self.end_time = timestamp;
+        self.rows.end = index + 1;
+    }
+}
+
+/// Splits a stream of rows into encounters.
+///
+/// Rows are pushed in file order together with their index, byte offset and
+/// timestamp. A pull that never sees its ENCOUNTER_END (a disconnect or a
+/// truncated log) is closed as a wipe when the next pull starts or when the
+/// segmenter is finished.
+#[derive(Default)]
+pub struct EncounterSegmenter {
+    layout: LogLayout,
+    current: Option<Encounter>,
+    last_index: usize,
+    encounters: Vec<Encounter>,
+}
+
+impl EncounterSegmenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, index: usize, offset: usize, timestamp: i64, row: &LogRow) {
+        self.last_index = index;
+
+        match row {
+            LogRow::CombatLogVersion(header) => self.layout = LogLayout::from(header),
+            LogRow::EncounterStart(start) => {
+                if let Some(mut unfinished) = self.current.take() {
+                    unfinished.rows.end = index;
+                    self.encounters.push(unfinished);
+                }
+                self.current = Some(Encounter::start(
+                    index,
+                    offset,
+                    timestamp,
+                    self.layout,
+                    start,
+                ));
+            }
+            LogRow::EncounterEnd(end) => {
+                if let Some(mut encounter) = self.current.take() {
+                    encounter.end(index, timestamp, end);
+                    self.encounters.push(encounter);
+                }
+            }
+            _ => {
+                if let Some(encounter) = self.current.as_mut() {
+                    encounter.end_time = timestamp;
+                    if let Some((unit, flags)) = row.advanced_unit() {
+                        encounter.track_health(unit, flags);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The pulls that ended so far.
+    pub fn encounters(&self) -> &[Encounter] {
+        &self.encounters
+    }
+
+    /// The pull in progress, if any.
+    pub fn current(&self) -> Option<&Encounter> {
+        self.current.as_ref()
+    }
+
+    pub fn finish(mut self) -> Vec<Encounter> {
+        if let Some(mut unfinished) = self.current.take() {
+            unfinished.rows.end = self.last_index + 1;
+            self.encounters.push(unfinished);
+        }
+
+        self.encounters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::cell::parse_log_csv;
+    use crate::parser::LogLayout;
+
+    #[test]
+    fn segments_pulls() {
+        let lines = [
+            "ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569",
+            "EMOTE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0000000000000000,nil,Enough!",
+            "ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,0,95012",
+            "EMOTE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0000000000000000,nil,Again!",
+            "ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569",
+            "ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,1,310442",
+            "ENCOUNTER_START,2680,\"Magmorax\",16,20,2569",
+        ];
+
+        let mut segmenter = EncounterSegmenter::new();
+        for (index, line) in lines.iter().enumerate() {
+            let (_, row) = parse_log_csv(line, &LogLayout::default()).unwrap();
+            segmenter.push(index, index * 100, index as i64 * 1000, &row);
+        }
+        let encounters = segmenter.finish();
+
+        assert_eq!(encounters.len(), 3);
+        assert_eq!(encounters[0].name, "Rashok, the Elder");
+        assert_eq!(encounters[0].rows, 0..3);
+        assert!(!encounters[0].success);
+        assert_eq!(encounters[1].rows, 4..6);
+        assert!(encounters[1].success);
+        assert_eq!(encounters[1].fight_time, 310442);
+        assert_eq!(encounters[1].offset, 400);
+        assert_eq!(encounters[1].start_time, 4000);
+        assert_eq!(encounters[1].end_time, 5000);
+        assert_eq!(encounters[2].id, 2680);
+        assert_eq!(encounters[2].rows, 6..7);
+        assert_eq!(encounters[2].boss_health, None);
+
+        // The boss is the hostile creature with the most health, adds and the
+        // friendly creature fighting it are ignored.
+        let add = "SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2515-19964-196102-000550239A,0000000000000000,1483954,1952835,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,488,488,-1,8,0,0,0,nil,nil,nil";
+        let boss = "SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2569-19964-201320-000550239A,0000000000000000,25000000,100000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,40000,40000,-1,8,0,0,0,nil,nil,nil";
+        let ally = "SWING_DAMAGE,Creature-0-4252-2569-19964-205000-000550239C,\"Ancient Protector\",0xa18,0x0,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0xa48,0x0,Creature-0-4252-2569-19964-205000-000550239C,0000000000000000,500000000,500000000,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,72,9000,9000,-1,1,0,0,0,nil,nil,nil";
+        let mut segmenter = EncounterSegmenter::new();
+        for (index, line) in [lines[0], add, boss, ally, add, lines[2]]
+            .iter()
+            .enumerate()
+        {
+            let (_, row) = parse_log_csv(line, &LogLayout::default()).unwrap();
+            segmenter.push(index, index * 100, index as i64 * 1000, &row);
+        }
+        let boss_health = segmenter.finish()[0].boss_health.clone().unwrap();
+        assert_eq!(boss_health.max, 100_000_000);
+        assert_eq!(boss_health.percent(), 25.0);
+    }
+}
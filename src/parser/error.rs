@@ -0,0 +1,154 @@
+use std::fmt;
+use std::io;
+
+use nom::error::{ErrorKind, FromExternalError, ParseError as NomParseError};
+use thiserror::Error;
+
+/// The nom result type used by the row parsers.
+pub type IResult<I, O> = nom::IResult<I, O, RowError<I>>;
+
+/// The number of fields a row was expected to have, next to the number it had.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldCount {
+    // Some events have more than one valid shape, e.g. misses with and without an amount.
+    pub expected: Vec<usize>,
+    pub actual: usize,
+}
+
+impl fmt::Display for FieldCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected: Vec<String> = self.expected.iter().map(usize::to_string).collect();
+        write!(
+            f,
+            "should have {} fields, had {}",
+            expected.join(" or "),
+            self.actual
+        )
+    }
+}
+
+/// The error produced by the nom parsers. Like `nom::error::Error`, but it also
+/// remembers field count mismatches so they can be reported.
+#[derive(Debug, PartialEq)]
+pub struct RowError<I> {
+    pub input: I,
+    pub code: ErrorKind,
+    pub field_count: Option<FieldCount>,
+}
+
+impl<I> RowError<I> {
+    pub fn new(input: I, code: ErrorKind) -> nom::Err<Self> {
+        nom::Err::Error(Self {
+            input,
+            code,
+            field_count: None,
+        })
+    }
+
+    pub fn field_count(input: I, expected: &[usize], actual: usize) -> nom::Err<Self> {
+        nom::Err::Error(Self {
+            input,
+            code: ErrorKind::LengthValue,
+            field_count: Some(FieldCount {
+                expected: expected.to_vec(),
+                actual,
+            }),
+        })
+    }
+}
+
+impl<I> NomParseError<I> for RowError<I> {
+    fn from_error_kind(input: I, code: ErrorKind) -> Self {
+        Self {
+            input,
+            code,
+            field_count: None,
+        }
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<I, E> FromExternalError<I, E> for RowError<I> {
+    fn from_external_error(input: I, code: ErrorKind, _: E) -> Self {
+        Self::from_error_kind(input, code)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    #[error("{0}")]
+    FieldCount(FieldCount),
+    #[error("could not be parsed ({0:?})")]
+    Syntax(ErrorKind),
+    #[error("has unparsed trailing data")]
+    TrailingData,
+    #[error("is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("could not be read: {0}")]
+    Io(String),
+}
+
+/// A line of the log that was skipped because it could not be parsed.
+#[derive(Debug, Error, PartialEq, Clone)]
+#[error("line {line} (byte {offset}): {event} {kind}")]
+pub struct ParseError {
+    // 1-based line number in the file.
+    pub line: usize,
+    // Byte offset in the file where parsing failed.
+    pub offset: usize,
+    // The event name of the row, or `?` if it could not be read.
+    pub event: String,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// The error for a log that could not be opened, before any line was read.
+    pub fn open(err: io::Error) -> Self {
+        Self {
+            line: 0,
+            offset: 0,
+            event: "?".to_string(),
+            kind: ParseErrorKind::Io(err.to_string()),
+        }
+    }
+
+    /// Builds the error for a line starting at byte `line_offset`, from the nom
+    /// error the line parser returned.
+    pub fn from_row_error(
+        line: usize,
+        line_offset: usize,
+        input: &str,
+        error: nom::Err<RowError<&str>>,
+    ) -> Self {
+        let (rest, kind) = match error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => (
+                error.input,
+                match error.field_count {
+                    Some(field_count) => ParseErrorKind::FieldCount(field_count),
+                    None => ParseErrorKind::Syntax(error.code),
+                },
+            ),
+            nom::Err::Incomplete(_) => ("", ParseErrorKind::Syntax(ErrorKind::Complete)),
+        };
+
+        Self {
+            line,
+            offset: line_offset + input.len() - rest.len(),
+            event: event_name(input),
+            kind,
+        }
+    }
+}
+
+// The event name follows the two spaces after the timestamp.
+pub fn event_name(input: &str) -> String {
+    input
+        .split_once("  ")
+        .and_then(|(_, row)| row.split(',').next())
+        .filter(|event| !event.is_empty())
+        .unwrap_or("?")
+        .to_string()
+}
@@ -0,0 +1,238 @@
+use bitflags::bitflags;
+
+use super::cell::LogCell;
+
+bitflags! {
+    /// COMBATLOG_OBJECT_* flags describing a unit relative to the player who wrote the log.
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+    pub struct UnitFlags: u32 {
+        const AFFILIATION_MINE = 0x0000_0001;
+        const AFFILIATION_PARTY = 0x0000_0002;
+        const AFFILIATION_RAID = 0x0000_0004;
+        const AFFILIATION_OUTSIDER = 0x0000_0008;
+        const REACTION_FRIENDLY = 0x0000_0010;
+        const REACTION_NEUTRAL = 0x0000_0020;
+        const REACTION_HOSTILE = 0x0000_0040;
+        const CONTROL_PLAYER = 0x0000_0100;
+        const CONTROL_NPC = 0x0000_0200;
+        const TYPE_PLAYER = 0x0000_0400;
+        const TYPE_NPC = 0x0000_0800;
+        const TYPE_PET = 0x0000_1000;
+        const TYPE_GUARDIAN = 0x0000_2000;
+        const TYPE_OBJECT = 0x0000_4000;
+        const TARGET = 0x0001_0000;
+        const FOCUS = 0x0002_0000;
+        const MAINTANK = 0x0004_0000;
+        const MAINASSIST = 0x0008_0000;
+        const NONE = 0x8000_0000;
+    }
+}
+
+bitflags! {
+    /// COMBATLOG_OBJECT_RAIDTARGET* flags, the raid marker on a unit.
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+    pub struct RaidFlags: u32 {
+        const STAR = 0x01;
+        const CIRCLE = 0x02;
+        const DIAMOND = 0x04;
+        const TRIANGLE = 0x08;
+        const MOON = 0x10;
+        const SQUARE = 0x20;
+        const CROSS = 0x40;
+        const SKULL = 0x80;
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Affiliation {
+    Mine,
+    Party,
+    Raid,
+    Outsider,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reaction {
+    Friendly,
+    Neutral,
+    Hostile,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Control {
+    Player,
+    Npc,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnitType {
+    Player,
+    Npc,
+    Pet,
+    Guardian,
+    Object,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RaidMarker {
+    Star,
+    Circle,
+    Diamond,
+    Triangle,
+    Moon,
+    Square,
+    Cross,
+    Skull,
+}
+
+impl UnitFlags {
+    pub fn affiliation(&self) -> Option<Affiliation> {
+        if self.contains(UnitFlags::AFFILIATION_MINE) {
+            Some(Affiliation::Mine)
+        } else if self.contains(UnitFlags::AFFILIATION_PARTY) {
+            Some(Affiliation::Party)
+        } else if self.contains(UnitFlags::AFFILIATION_RAID) {
+            Some(Affiliation::Raid)
+        } else if self.contains(UnitFlags::AFFILIATION_OUTSIDER) {
+            Some(Affiliation::Outsider)
+        } else {
+            None
+        }
+    }
+
+    pub fn reaction(&self) -> Option<Reaction> {
+        if self.contains(UnitFlags::REACTION_FRIENDLY) {
+            Some(Reaction::Friendly)
+        } else if self.contains(UnitFlags::REACTION_NEUTRAL) {
+            Some(Reaction::Neutral)
+        } else if self.contains(UnitFlags::REACTION_HOSTILE) {
+            Some(Reaction::Hostile)
+        } else {
+            None
+        }
+    }
+
+    pub fn control(&self) -> Option<Control> {
+        if self.contains(UnitFlags::CONTROL_PLAYER) {
+            Some(Control::Player)
+        } else if self.contains(UnitFlags::CONTROL_NPC) {
+            Some(Control::Npc)
+        } else {
+            None
+        }
+    }
+
+    pub fn unit_type(&self) -> Option<UnitType> {
+        if self.contains(UnitFlags::TYPE_PLAYER) {
+            Some(UnitType::Player)
+        } else if self.contains(UnitFlags::TYPE_NPC) {
+            Some(UnitType::Npc)
+        } else if self.contains(UnitFlags::TYPE_PET) {
+            Some(UnitType::Pet)
+        } else if self.contains(UnitFlags::TYPE_GUARDIAN) {
+            Some(UnitType::Guardian)
+        } else if self.contains(UnitFlags::TYPE_OBJECT) {
+            Some(UnitType::Object)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_hostile(&self) -> bool {
+        self.reaction() == Some(Reaction::Hostile)
+    }
+
+    pub fn is_friendly(&self) -> bool {
+        self.reaction() == Some(Reaction::Friendly)
+    }
+
+    pub fn is_player(&self) -> bool {
+        self.unit_type() == Some(UnitType::Player)
+    }
+
+    pub fn is_npc(&self) -> bool {
+        self.unit_type() == Some(UnitType::Npc)
+    }
+
+    // Anyone in the group of the player who wrote the log, including that player.
+    pub fn is_in_group(&self) -> bool {
+        matches!(
+            self.affiliation(),
+            Some(Affiliation::Mine | Affiliation::Party | Affiliation::Raid)
+        )
+    }
+}
+
+impl RaidFlags {
+    pub fn marker(&self) -> Option<RaidMarker> {
+        [
+            (RaidFlags::STAR, RaidMarker::Star),
+            (RaidFlags::CIRCLE, RaidMarker::Circle),
+            (RaidFlags::DIAMOND, RaidMarker::Diamond),
+            (RaidFlags::TRIANGLE, RaidMarker::Triangle),
+            (RaidFlags::MOON, RaidMarker::Moon),
+            (RaidFlags::SQUARE, RaidMarker::Square),
+            (RaidFlags::CROSS, RaidMarker::Cross),
+            (RaidFlags::SKULL, RaidMarker::Skull),
+        ]
+        .into_iter()
+        .find(|(flag, _)| self.contains(*flag))
+        .map(|(_, marker)| marker)
+    }
+}
+
+// Flags are written as hex, which the cell parser hands over without the 0x prefix.
+pub(super) fn parse_hex_bits(cell: LogCell) -> u32 {
+    match cell {
+        LogCell::Str(v) => u32::from_str_radix(&v, 16).unwrap_or_default(),
+        LogCell::Integer(v) => v as u32,
+        _ => 0,
+    }
+}
+
+impl<'a> From<LogCell<'a>> for UnitFlags {
+    fn from(cell: LogCell) -> Self {
+        UnitFlags::from_bits_retain(parse_hex_bits(cell))
+    }
+}
+
+impl<'a> From<LogCell<'a>> for RaidFlags {
+    fn from(cell: LogCell) -> Self {
+        RaidFlags::from_bits_retain(parse_hex_bits(cell))
+    }
+}
+
+/// The flags of both units involved in a row.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LogRowFlags {
+    pub source: UnitFlags,
+    pub sourceRaid: RaidFlags,
+    pub dest: UnitFlags,
+    pub destRaid: RaidFlags,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_flags() {
+        let player = UnitFlags::from(LogCell::Str("512".into()));
+        assert_eq!(player.affiliation(), Some(Affiliation::Party));
+        assert!(player.is_friendly());
+        assert!(player.is_player());
+        assert_eq!(player.control(), Some(Control::Player));
+
+        let add = UnitFlags::from(LogCell::Str("a48".into()));
+        assert_eq!(add.affiliation(), Some(Affiliation::Outsider));
+        assert!(add.is_hostile());
+        assert!(add.is_npc());
+
+        assert!(UnitFlags::from(LogCell::Str("80000000".into())).contains(UnitFlags::NONE));
+        assert_eq!(
+            RaidFlags::from(LogCell::Str("80".into())).marker(),
+            Some(RaidMarker::Skull)
+        );
+        assert_eq!(RaidFlags::from(LogCell::Str("0".into())).marker(), None);
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+
+use super::cell::LogCell;
+
+/// The id fields shared by creatures, pets, vehicles, game objects and vignettes,
+/// e.g. `Creature-0-4252-2515-19964-196102-000550239A`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct UnitGuid {
+    pub server_id: u32,
+    pub instance_id: u32,
+    pub zone_uid: u32,
+    // Always 0 for vignettes.
+    pub npc_id: u32,
+    pub spawn_uid: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum Guid {
+    // `Player-1379-0A9FF58F`, where the server id identifies the realm.
+    Player { server_id: u32, player_uid: u32 },
+    Creature(UnitGuid),
+    Pet(UnitGuid),
+    Vehicle(UnitGuid),
+    GameObject(UnitGuid),
+    // `Item-1379-0-400000033D4D2E0B`
+    Item { server_id: u32, spawn_uid: u64 },
+    Vignette(UnitGuid),
+    // Written as `0000000000000000` when there is no unit.
+    Nil,
+    // Any other kind of GUID (BattlePet, Cast, ...), or one we failed to decode,
+    // kept as written so that different units stay apart.
+    Unknown(Box<str>),
+}
+
+impl Guid {
+    pub fn is_player(&self) -> bool {
+        matches!(self, Guid::Player { .. })
+    }
+
+    pub fn server_id(&self) -> Option<u32> {
+        match self {
+            Guid::Player { server_id, .. } | Guid::Item { server_id, .. } => Some(*server_id),
+            Guid::Creature(unit)
+            | Guid::Pet(unit)
+            | Guid::Vehicle(unit)
+            | Guid::GameObject(unit)
+            | Guid::Vignette(unit) => Some(unit.server_id),
+            Guid::Nil | Guid::Unknown(_) => None,
+        }
+    }
+
+    pub fn npc_id(&self) -> Option<u32> {
+        match self {
+            Guid::Creature(unit) | Guid::Pet(unit) | Guid::Vehicle(unit) => Some(unit.npc_id),
+            _ => None,
+        }
+    }
+}
+
+fn parse_unit(parts: &[&str]) -> Option<UnitGuid> {
+    match parts {
+        [_, server_id, instance_id, zone_uid, npc_id, spawn_uid] => Some(UnitGuid {
+            server_id: server_id.parse().ok()?,
+            instance_id: instance_id.parse().ok()?,
+            zone_uid: zone_uid.parse().ok()?,
+            npc_id: npc_id.parse().ok()?,
+            spawn_uid: u64::from_str_radix(spawn_uid, 16).ok()?,
+        }),
+        _ => None,
+    }
+}
+
+fn parse_guid(input: &str) -> Option<Guid> {
+    let mut parts = input.split('-');
+    let kind = parts.next()?;
+    let parts: Vec<&str> = parts.collect();
+
+    match kind {
+        "Player" => match parts[..] {
+            [server_id, player_uid] => Some(Guid::Player {
+                server_id: server_id.parse().ok()?,
+                player_uid: u32::from_str_radix(player_uid, 16).ok()?,
+            }),
+            _ => None,
+        },
+        "Item" => match parts[..] {
+            [server_id, _, spawn_uid] => Some(Guid::Item {
+                server_id: server_id.parse().ok()?,
+                spawn_uid: u64::from_str_radix(spawn_uid, 16).ok()?,
+            }),
+            _ => None,
+        },
+        "Creature" => parse_unit(&parts).map(Guid::Creature),
+        "Pet" => parse_unit(&parts).map(Guid::Pet),
+        "Vehicle" => parse_unit(&parts).map(Guid::Vehicle),
+        "GameObject" => parse_unit(&parts).map(Guid::GameObject),
+        "Vignette" => parse_unit(&parts).map(Guid::Vignette),
+        _ if input.bytes().all(|c| c == b'0') => Some(Guid::Nil),
+        _ => None,
+    }
+}
+
+impl From<&str> for Guid {
+    fn from(input: &str) -> Self {
+        parse_guid(input).unwrap_or_else(|| Guid::Unknown(input.into()))
+    }
+}
+
+impl<'a> From<LogCell<'a>> for Guid {
+    fn from(cell: LogCell) -> Self {
+        match cell {
+            // The nil GUID is all digits, so it is tokenized as a number.
+            LogCell::Integer(0) => Guid::Nil,
+            LogCell::Str(v) => Guid::from(v.as_ref()),
+            LogCell::Integer(v) => Guid::Unknown(v.to_string().into()),
+            cell => Guid::Unknown(format!("{:?}", cell).into()),
+        }
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, unit) = match self {
+            Guid::Player {
+                server_id,
+                player_uid,
+            } => return write!(f, "Player-{}-{:08X}", server_id, player_uid),
+            Guid::Item {
+                server_id,
+                spawn_uid,
+            } => return write!(f, "Item-{}-0-{:016X}", server_id, spawn_uid),
+            Guid::Nil => return write!(f, "0000000000000000"),
+            Guid::Unknown(text) => return write!(f, "{}", text),
+            Guid::Creature(unit) => ("Creature", unit),
+            Guid::Pet(unit) => ("Pet", unit),
+            Guid::Vehicle(unit) => ("Vehicle", unit),
+            Guid::GameObject(unit) => ("GameObject", unit),
+            Guid::Vignette(unit) => ("Vignette", unit),
+        };
+
+        write!(
+            f,
+            "{}-0-{}-{}-{}-{}-{:010X}",
+            kind, unit.server_id, unit.instance_id, unit.zone_uid, unit.npc_id, unit.spawn_uid
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_guids() {
+        let player = Guid::from("Player-1379-0A9FF58F");
+        assert_eq!(
+            player,
+            Guid::Player {
+                server_id: 1379,
+                player_uid: 0x0A9FF58F
+            }
+        );
+        assert_eq!(player.to_string(), "Player-1379-0A9FF58F");
+
+        let creature = Guid::from("Creature-0-4252-2515-19964-196102-000550239A");
+        assert_eq!(creature.npc_id(), Some(196102));
+        assert_eq!(creature.server_id(), Some(4252));
+        assert_eq!(
+            creature.to_string(),
+            "Creature-0-4252-2515-19964-196102-000550239A"
+        );
+
+        assert!(matches!(
+            Guid::from("Pet-0-4252-2515-19964-165189-0104A29B4F"),
+            Guid::Pet(_)
+        ));
+        assert_eq!(Guid::from(LogCell::Integer(0)), Guid::Nil);
+        let battle_pet = Guid::from("BattlePet-0-000012A4C6B0");
+        assert_eq!(battle_pet.to_string(), "BattlePet-0-000012A4C6B0");
+        assert_ne!(battle_pet, Guid::from("BattlePet-0-000012A4C6B1"));
+    }
+}
@@ -0,0 +1,76 @@
+use super::cell::LogCombatLogVersion;
+
+// First log version with the Dragonflight field layout: advanced params carry the
+// total absorbs on the unit, and damage and heal suffixes carry the unmitigated
+// amount (replacing the offhand flag on damage).
+const MODERN_LAYOUT_VERSION: i64 = 20;
+
+/// The number of fields in the damage suffix. Modern layouts write the
+/// unmitigated amount where older ones write the offhand flag, so the count is
+/// the same for every version.
+pub const DAMAGE_FIELDS: usize = 10;
+
+/// The field layout of a log, as declared by its COMBAT_LOG_VERSION header.
+///
+/// Retail and Classic clients write different columns for the same events, and
+/// advanced params are only written when advanced combat logging is enabled.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LogLayout {
+    pub version: i64,
+    pub advanced: bool,
+}
+
+impl Default for LogLayout {
+    // Retail with advanced logging enabled, used until a header has been read.
+    fn default() -> Self {
+        Self {
+            version: MODERN_LAYOUT_VERSION,
+            advanced: true,
+        }
+    }
+}
+
+impl From<&LogCombatLogVersion<'_>> for LogLayout {
+    fn from(header: &LogCombatLogVersion) -> Self {
+        Self {
+            version: header.version,
+            advanced: header.advancedLogEnabled,
+        }
+    }
+}
+
+impl LogLayout {
+    pub fn has_total_absorbs(&self) -> bool {
+        self.version >= MODERN_LAYOUT_VERSION
+    }
+
+    pub fn has_base_amount(&self) -> bool {
+        self.version >= MODERN_LAYOUT_VERSION
+    }
+
+    pub fn advanced_fields(&self) -> usize {
+        match (self.advanced, self.has_total_absorbs()) {
+            (false, _) => 0,
+            (true, true) => 17,
+            (true, false) => 16,
+        }
+    }
+
+    // The missed amount, the unmitigated amount and the crit flag written after
+    // partial misses (ABSORB, BLOCK, RESIST).
+    pub fn partial_miss_fields(&self) -> usize {
+        if self.has_base_amount() {
+            3
+        } else {
+            2
+        }
+    }
+
+    pub fn heal_fields(&self) -> usize {
+        if self.has_base_amount() {
+            5
+        } else {
+            4
+        }
+    }
+}
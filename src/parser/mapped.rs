@@ -0,0 +1,167 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use super::error::ParseError;
+use super::layout::LogLayout;
+use super::parallel::ParRows;
+use super::rows::{Event, LineParser};
+use super::summary::LogSummary;
+
+/// A log file mapped into memory. Rows read from it borrow their strings from
+/// the map instead of copying them, so a whole raid night can be kept around
+/// without allocating for every row.
+pub struct LogFile {
+    map: Mmap,
+}
+
+impl LogFile {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only read. The game only ever appends to a log,
+        // which leaves the mapped range untouched; a log that is truncated while
+        // it is mapped would be undefined behaviour.
+        let map = unsafe { Mmap::map(&file)? };
+
+        Ok(Self { map })
+    }
+
+    /// Streams the rows of the file without keeping them.
+    pub fn rows(&self) -> MappedRows<'_> {
+        MappedRows::new(&self.map, LineParser::new())
+    }
+
+    /// Streams the rows of the file from byte `offset` on, with `layout` in
+    /// effect there. Line numbers and timestamps are counted from `offset`.
+    pub fn rows_from(&self, offset: usize, layout: LogLayout) -> MappedRows<'_> {
+        let data = self.map.get(offset..).unwrap_or_default();
+        MappedRows::new(data, LineParser::starting_at(offset, layout))
+    }
+
+    /// Like `rows`, but parses ahead on all cores. Rows are still yielded in order.
+    pub fn par_rows(&self) -> ParRows<'_> {
+        ParRows::new(&self.map)
+    }
+
+    /// Parses every row of the file, keeping them for as long as the file is open.
+    pub fn parse(&self) -> ParsedLog<'_> {
+        self.rows().collect()
+    }
+
+    /// Like `parse`, but on all cores.
+    pub fn parse_parallel(&self) -> ParsedLog<'_> {
+        self.par_rows().collect()
+    }
+
+    /// The zones and pulls of the file, without parsing every row.
+    pub fn summary(&self) -> LogSummary {
+        LogSummary::scan(&self.map)
+    }
+}
+
+/// Iterator over the rows of a `LogFile`, created by `LogFile::rows`.
+pub struct MappedRows<'a> {
+    data: &'a [u8],
+    lines: LineParser,
+}
+
+impl<'a> MappedRows<'a> {
+    pub(super) fn new(data: &'a [u8], lines: LineParser) -> Self {
+        Self { data, lines }
+    }
+
+    pub(super) fn line_parser(&self) -> &LineParser {
+        &self.lines
+    }
+}
+
+impl<'a> Iterator for MappedRows<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.data.is_empty() {
+            let end = self
+                .data
+                .iter()
+                .position(|c| *c == b'\n')
+                .map_or(self.data.len(), |newline| newline + 1);
+            let (line, rest) = self.data.split_at(end);
+            self.data = rest;
+
+            match self.lines.parse(line) {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+}
+
+/// Every row of a log file, borrowing from the `LogFile` it was parsed from.
+pub struct ParsedLog<'a> {
+    pub events: Vec<Event<'a>>,
+    // Lines that were skipped because they could not be parsed.
+    pub errors: Vec<ParseError>,
+}
+
+impl<'a> FromIterator<Result<Event<'a>, ParseError>> for ParsedLog<'a> {
+    fn from_iter<I: IntoIterator<Item = Result<Event<'a>, ParseError>>>(rows: I) -> Self {
+        let mut log = ParsedLog {
+            events: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        for row in rows {
+            match row {
+                Ok(event) => log.events.push(event),
+                Err(err) => log.errors.push(err),
+            }
+        }
+
+        log
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::parser::{LogCell, LogRow};
+
+    #[test]
+    fn parses_mapped_file() {
+        let path =
+            std::env::temp_dir().join(format!("wow-log-analyzer-{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "4/29 20:15:03.123  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\r\n\
+            4/29 20:15:04.000  ENCOUNTER_END,2688\r\n\
+            4/29 20:16:38.135  ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,0,95012\r\n",
+        )
+        .unwrap();
+
+        let file = LogFile::open(&path).unwrap();
+        let log = file.parse();
+
+        assert_eq!(log.events.len(), 2);
+        assert_eq!(log.errors.len(), 1);
+        assert_eq!(log.errors[0].line, 2);
+        match &log.events[0].row {
+            LogRow::EncounterStart(start) => assert!(matches!(
+                start.encounterName,
+                LogCell::Str(Cow::Borrowed("Rashok, the Elder"))
+            )),
+            row => panic!("Expected encounter start, got {:?}", row),
+        }
+        assert_eq!(log.events[1].timestamp, 95012);
+
+        drop(log);
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
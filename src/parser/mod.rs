@@ -1,20 +1,58 @@
+mod cache;
 mod cell;
+mod columns;
+mod combatant;
+mod encounter;
+mod error;
+mod flags;
+mod guid;
+mod layout;
+mod mapped;
+mod owned;
+mod parallel;
+mod rows;
+mod school;
+mod summary;
+mod tail;
+mod time;
 
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead};
+use std::path::Path;
 
-use anyhow;
-use nom::combinator::map;
-use nom::error::ErrorKind;
-use nom::sequence::tuple;
-use nom::Err;
-use nom::{bytes::complete::tag, character::complete::digit1, sequence::separated_pair, IResult};
-use thiserror::Error;
+use nom::{bytes::complete::tag, sequence::separated_pair};
 
-use self::cell::{parse_log_csv, LogCell, LogEventDateTime, LogRow};
+pub use self::cache::{CacheWriter, CachedLog, CachedRows, LogCache};
+pub use self::cell::{parse_log_csv, LogAdvancedParams, LogCell, LogRow};
+pub use self::encounter::{BossHealth, Encounter, EncounterSegmenter};
+use self::error::event_name;
+pub use self::error::{FieldCount, ParseError, ParseErrorKind};
+pub use self::flags::{
+    Affiliation, Control, LogRowFlags, RaidFlags, RaidMarker, Reaction, UnitFlags, UnitType,
+};
+pub use self::guid::{Guid, UnitGuid};
+pub use self::layout::LogLayout;
+pub use self::mapped::{LogFile, MappedRows, ParsedLog};
+pub use self::owned::IntoStatic;
+pub use self::parallel::ParRows;
+pub use self::rows::{Event, Rows};
+pub use self::school::SpellSchool;
+pub use self::summary::LogSummary;
+pub use self::tail::LogTail;
+use self::time::parse_date_time;
+pub use self::time::{LogClock, LogEventDateTime};
 
 pub struct Parser<'a> {
     lines: Vec<String>,
     parsed_lines: Vec<Vec<LogCell<'a>>>,
+    errors: Vec<ParseError>,
+    from_cache: bool,
+    cache_error: Option<io::Error>,
+}
+
+impl Default for Parser<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Parser<'_> {
@@ -22,61 +60,254 @@ impl Parser<'_> {
         Self {
             lines: Vec::new(),
             parsed_lines: Vec::new(),
+            errors: Vec::new(),
+            from_cache: false,
+            cache_error: None,
+        }
+    }
+
+    /// The lines skipped by the last call to `parse_file`.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Whether the last call to `parse_file` read the log from the cache.
+    pub fn from_cache(&self) -> bool {
+        self.from_cache
+    }
+
+    /// Why the log read by the last call to `parse_file` could not be cached.
+    /// The log is still usable without a cache, it is just parsed again next time.
+    pub fn cache_error(&self) -> Option<&io::Error> {
+        self.cache_error.as_ref()
+    }
+
+    /// Streams the rows of a log from any reader, e.g. a file, stdin or a test string.
+    pub fn rows<R: BufRead>(reader: R) -> Rows<R> {
+        Rows::new(reader)
+    }
+
+    /// The pulls in a log. Lines that cannot be parsed are skipped and kept in
+    /// `errors`, only a log that cannot be opened fails.
+    pub fn parse_file(&mut self, file: impl AsRef<Path>) -> Result<Vec<Encounter>, ParseError> {
+        let file = file.as_ref();
+        let cache = LogCache::in_user_cache_dir();
+        self.from_cache = false;
+        self.cache_error = None;
+
+        if let Some(cached) = cache.as_ref().and_then(|cache| cache.load(file)) {
+            let mut rows = cached.rows();
+            let encounters = self.segment(rows.by_ref(), None);
+            if !rows.is_corrupt() {
+                self.from_cache = true;
+                return Ok(encounters);
+            }
+        }
+
+        let log = LogFile::open(file).map_err(ParseError::open)?;
+        let mut writer = match cache.map(|cache| cache.writer(file)) {
+            Some(Ok(writer)) => Some(writer),
+            Some(Err(err)) => {
+                self.cache_error = Some(err);
+                None
+            }
+            None => None,
+        };
+        let encounters = self.segment(log.par_rows(), writer.as_mut());
+        if let Some(Err(err)) = writer.map(CacheWriter::finish) {
+            self.cache_error = Some(err);
+        }
+
+        Ok(encounters)
+    }
+
+    /// Runs `analyze` over the rows of `encounter`, a pull `parse_file` found in
+    /// the same file, together with what `seed` made of the rows before the pull,
+    /// e.g. the owners of the pets summoned before it. The rows are read from the
+    /// cache when there is one; should it turn out to be corrupt, both are run
+    /// again over the rows parsed from the log itself.
+    pub fn analyze_encounter<S, T>(
+        file: impl AsRef<Path>,
+        encounter: &Encounter,
+        seed: impl Fn(&mut dyn Iterator<Item = Event<'_>>) -> S,
+        analyze: impl Fn(S, &mut dyn Iterator<Item = Event<'_>>) -> T,
+    ) -> Result<T, ParseError> {
+        let file = file.as_ref();
+        let rows = encounter.rows.clone();
+        let in_pull = |seeded: S, events: &mut dyn Iterator<Item = Event<'_>>| {
+            let mut events = events
+                .skip_while(|event| event.line - 1 < rows.start)
+                .take_while(|event| event.line - 1 < rows.end);
+            analyze(seeded, &mut events)
+        };
+
+        let cache = LogCache::in_user_cache_dir();
+        if let Some(cached) = cache.as_ref().and_then(|cache| cache.load(file)) {
+            let mut rows = cached.rows();
+            let mut events = rows.by_ref().filter_map(Result::ok).peekable();
+            let seeded = seed(&mut std::iter::from_fn(|| {
+                events.next_if(|event| event.line - 1 < encounter.rows.start)
+            }));
+            let result = in_pull(seeded, &mut events);
+            if !rows.is_corrupt() {
+                return Ok(result);
+            }
         }
+
+        let log = LogFile::open(file).map_err(ParseError::open)?;
+        let seeded = seed(
+            &mut log
+                .par_rows()
+                .filter_map(Result::ok)
+                .take_while(|event| event.offset < encounter.offset),
+        );
+        // Line numbers and timestamps are counted from the start of the pull.
+        let mut events = log
+            .rows_from(encounter.offset, encounter.layout)
+            .filter_map(Result::ok)
+            .map(|mut event| {
+                event.line += rows.start;
+                event.timestamp += encounter.start_time;
+                event
+            });
+        Ok(in_pull(seeded, &mut events))
     }
 
-    pub fn parse_file(&self, file: String) {
-        let time_start = std::time::Instant::now();
-        let file = std::fs::File::open(file).expect("Could not open file");
-        let reader = BufReader::new(file);
-
-        let mut num_lines = 0;
-
-        for line in reader.lines() {
-            num_lines += 1;
-            let strline = line.unwrap();
-            let (remainder, _, row) = parse_line(strline.as_str());
-            if remainder != "" {
-                if row != LogRow::NotSupported {
-                    println!(
-                        "Failed to parse remainder: {}. Last cell: {:?}. Row: {:?}",
-                        remainder, row, strline
-                    );
+    fn segment<'a>(
+        &mut self,
+        rows: impl Iterator<Item = Result<Event<'a>, ParseError>>,
+        mut cache: Option<&mut CacheWriter>,
+    ) -> Vec<Encounter> {
+        let mut segmenter = EncounterSegmenter::new();
+        self.errors.clear();
+
+        for row in rows {
+            if let Some(cache) = cache.as_mut() {
+                cache.push(&row);
+            }
+            match row {
+                Ok(event) => {
+                    segmenter.push(event.line - 1, event.offset, event.timestamp, &event.row)
                 }
+                Err(err) => self.errors.push(err),
             }
         }
 
-        println!("Parsed {} lines in {:?}", num_lines, time_start.elapsed());
+        segmenter.finish()
     }
 }
 
-fn parse_line(input: &str) -> (&str, LogEventDateTime, LogRow) {
-    let parsed_input = separated_pair(parse_date_time, tag("  "), parse_log_csv)(input);
-    if parsed_input.is_err() {
-        panic!("Failed to parse input: {:?}: {:?}", input, parsed_input);
+// `line` is the 1-based line number and `offset` the byte offset of the line in
+// the file, both only used to report errors.
+fn parse_line<'a>(
+    line: usize,
+    offset: usize,
+    input: &'a str,
+    layout: &LogLayout,
+) -> Result<(LogEventDateTime, LogRow<'a>), ParseError> {
+    let (remainder, (date_time, row)) = separated_pair(parse_date_time, tag("  "), |input| {
+        parse_log_csv(input, layout)
+    })(input)
+    .map_err(|err| ParseError::from_row_error(line, offset, input, err))?;
+
+    // Unsupported events are not parsed past their name.
+    if !remainder.is_empty() && row != LogRow::NotSupported {
+        return Err(ParseError {
+            line,
+            offset: offset + input.len() - remainder.len(),
+            event: event_name(input),
+            kind: ParseErrorKind::TrailingData,
+        });
     }
-    let (remainder, result) = parsed_input.unwrap();
 
-    (remainder, result.0, result.1)
+    Ok((date_time, row))
 }
 
-fn parse_date(input: &str) -> IResult<&str, (&str, &str)> {
-    separated_pair(digit1, tag("/"), digit1)(input)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn parse_time(input: &str) -> IResult<&str, (&str, &str, &str, &str, &str, &str, &str)> {
-    tuple((digit1, tag(":"), digit1, tag(":"), digit1, tag("."), digit1))(input)
-}
+    #[test]
+    fn reports_malformed_lines() {
+        let layout = LogLayout::default();
+        let short_damage = "4/29 20:15:03.123  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,213709,\"Brambles\",0x8,488";
+        let err = parse_line(7, 100, short_damage, &layout).unwrap_err();
+        assert_eq!(err.line, 7);
+        assert_eq!(err.offset, 119);
+        assert_eq!(err.event, "SPELL_DAMAGE");
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::FieldCount(FieldCount {
+                expected: vec![38],
+                actual: 12
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "line 7 (byte 119): SPELL_DAMAGE should have 38 fields, had 12"
+        );
 
-fn parse_date_time(input: &str) -> IResult<&str, LogEventDateTime> {
-    let parser = separated_pair(parse_date, tag(" "), parse_time);
+        let err = parse_line(1, 0, "4/29 20:15  SPELL_DAMAGE", &layout).unwrap_err();
+        assert_eq!(err.event, "SPELL_DAMAGE");
+        assert!(matches!(err.kind, ParseErrorKind::Syntax(_)));
 
-    map(parser, |(date, time)| LogEventDateTime {
-        month: date.0,
-        day: date.1,
-        hour: time.0,
-        minute: time.2,
-        second: time.4,
-        ms: time.6,
-    })(input)
+        let unsupported =
+            "4/29 20:15:03.123  MAP_CHANGE,2166,\"Aberrus, the Shadowed Crucible\",-2125.7,-2675.5,-1823.9,-2372.2";
+        assert_eq!(
+            parse_line(1, 0, unsupported, &layout).unwrap().1,
+            LogRow::NotSupported
+        );
+    }
+
+    #[test]
+    fn analyzes_pull_from_its_offset() {
+        let path =
+            std::env::temp_dir().join(format!("wow-log-analyzer-pull-{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "4/29 20:15:03.123  COMBAT_LOG_VERSION,9,ADVANCED_LOG_ENABLED,0,BUILD_VERSION,3.4.3,PROJECT_ID,11\n\
+            4/29 20:15:04.000  ENCOUNTER_START,1090,\"Sartharion\",4,25,615\n\
+            4/29 20:15:05.000  ENCOUNTER_END,1090,\"Sartharion\",4,25,0,1000\n\
+            4/29 20:16:00.000  ENCOUNTER_START,1090,\"Sartharion\",4,25,615\n\
+            4/29 20:16:01.500  SPELL_DAMAGE,Player-4395-01C5EEA4,\"Yerrog-Pagle\",0x511,0x0,Creature-0-4395-615-2425-28860-00004DD9A8,\"Sartharion\",0x10a48,0x0,48638,\"Sinister Strike\",0x1,2215,0,1,0,0,0,1,nil,nil,1\n\
+            4/29 20:16:02.000  ENCOUNTER_END,1090,\"Sartharion\",4,25,1,2000\n\
+            4/29 20:16:03.000  ZONE_CHANGE,2569,\"Aberrus, the Shadowed Crucible\",16\n",
+        )
+        .unwrap();
+
+        let log = LogFile::open(&path).unwrap();
+        let events: Vec<Event> = log.rows().filter_map(Result::ok).collect();
+        let mut segmenter = EncounterSegmenter::new();
+        for event in &events {
+            segmenter.push(event.line - 1, event.offset, event.timestamp, &event.row);
+        }
+        let encounters = segmenter.finish();
+
+        // The second pull, parsed on its own with the layout of the header.
+        let pull = &encounters[1];
+        assert_eq!(pull.layout.version, 9);
+        let positions = |events: &mut dyn Iterator<Item = Event<'_>>| -> Vec<_> {
+            events
+                .map(|event| (event.line, event.offset, event.timestamp))
+                .collect()
+        };
+        let (before, analyzed) =
+            Parser::analyze_encounter(&path, pull, positions, |before, events| {
+                (before, positions(events))
+            })
+            .unwrap();
+        let expected = positions(&mut events.into_iter());
+        assert_eq!(before, expected[..3]);
+        assert_eq!(analyzed, expected[3..6]);
+
+        drop(log);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reports_missing_log() {
+        let missing = std::env::temp_dir().join("wow-log-analyzer-missing.txt");
+        let err = Parser::new().parse_file(missing).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Io(_)));
+    }
 }
@@ -0,0 +1,139 @@
+use std::borrow::Cow;
+
+use super::cell::row_structs;
+use super::cell::*;
+use super::combatant::CombatantInfo;
+use super::flags::{RaidFlags, UnitFlags};
+use super::guid::Guid;
+use super::school::SpellSchool;
+
+/// Detaches a parsed value from the line it was parsed from, copying the
+/// strings it borrows so it can outlive the line.
+pub trait IntoStatic {
+    type Static: 'static;
+
+    fn into_static(self) -> Self::Static;
+}
+
+// Types that do not borrow from the line.
+macro_rules! impl_into_static_identity {
+    ($($name:ty),* $(,)?) => {
+        $(
+            impl IntoStatic for $name {
+                type Static = $name;
+
+                fn into_static(self) -> Self::Static {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_into_static_identity!(
+    bool,
+    i64,
+    Guid,
+    UnitFlags,
+    RaidFlags,
+    SpellSchool,
+    AuraType,
+    CombatantInfo,
+);
+
+impl<T: IntoStatic> IntoStatic for Option<T> {
+    type Static = Option<T::Static>;
+
+    fn into_static(self) -> Self::Static {
+        self.map(T::into_static)
+    }
+}
+
+impl<'a> IntoStatic for Cow<'a, str> {
+    type Static = Cow<'static, str>;
+
+    fn into_static(self) -> Self::Static {
+        Cow::Owned(self.into_owned())
+    }
+}
+
+impl<'a> IntoStatic for LogCell<'a> {
+    type Static = LogCell<'static>;
+
+    fn into_static(self) -> Self::Static {
+        match self {
+            LogCell::Integer(v) => LogCell::Integer(v),
+            LogCell::Float(v) => LogCell::Float(v),
+            LogCell::MultiPowerCell(v) => LogCell::MultiPowerCell(v),
+            LogCell::Str(v) => LogCell::Str(v.into_static()),
+            LogCell::Array(v) => {
+                LogCell::Array(v.into_iter().map(IntoStatic::into_static).collect())
+            }
+        }
+    }
+}
+
+// Every field is converted with `into_static`.
+macro_rules! impl_into_static {
+    ($($name:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl<'a> IntoStatic for $name<'a> {
+                type Static = $name<'static>;
+
+                fn into_static(self) -> Self::Static {
+                    $name {
+                        $($field: self.$field.into_static()),*
+                    }
+                }
+            }
+        )*
+    };
+}
+
+row_structs!(impl_into_static);
+
+impl<'a> IntoStatic for LogRow<'a> {
+    type Static = LogRow<'static>;
+
+    fn into_static(self) -> Self::Static {
+        match self {
+            LogRow::Emote(row) => LogRow::Emote(row.into_static()),
+            LogRow::SpellCastSuccess(row) => LogRow::SpellCastSuccess(row.into_static()),
+            LogRow::SpellDamage(row) => LogRow::SpellDamage(row.into_static()),
+            LogRow::RangeDamage(row) => LogRow::RangeDamage(row.into_static()),
+            LogRow::SpellHeal(row) => LogRow::SpellHeal(row.into_static()),
+            LogRow::SpellMissed(row) => LogRow::SpellMissed(row.into_static()),
+            LogRow::SpellEnergize(row) => LogRow::SpellEnergize(row.into_static()),
+            LogRow::SpellDrain(row) => LogRow::SpellDrain(row.into_static()),
+            LogRow::SpellLeech(row) => LogRow::SpellLeech(row.into_static()),
+            LogRow::SpellPeriodicDamage(row) => LogRow::SpellPeriodicDamage(row.into_static()),
+            LogRow::SpellPeriodicHeal(row) => LogRow::SpellPeriodicHeal(row.into_static()),
+            LogRow::SpellPeriodicMissed(row) => LogRow::SpellPeriodicMissed(row.into_static()),
+            LogRow::SpellPeriodicEnergize(row) => LogRow::SpellPeriodicEnergize(row.into_static()),
+            LogRow::SpellPeriodicDrain(row) => LogRow::SpellPeriodicDrain(row.into_static()),
+            LogRow::SpellPeriodicLeech(row) => LogRow::SpellPeriodicLeech(row.into_static()),
+            LogRow::SpellAuraApplied(row) => LogRow::SpellAuraApplied(row.into_static()),
+            LogRow::SpellAuraRemoved(row) => LogRow::SpellAuraRemoved(row.into_static()),
+            LogRow::SpellAuraRefresh(row) => LogRow::SpellAuraRefresh(row.into_static()),
+            LogRow::SpellAuraAppliedDose(row) => LogRow::SpellAuraAppliedDose(row.into_static()),
+            LogRow::SpellAuraRemovedDose(row) => LogRow::SpellAuraRemovedDose(row.into_static()),
+            LogRow::SpellAuraBroken(row) => LogRow::SpellAuraBroken(row.into_static()),
+            LogRow::SpellAuraBrokenSpell(row) => LogRow::SpellAuraBrokenSpell(row.into_static()),
+            LogRow::SpellAbsorbed(row) => LogRow::SpellAbsorbed(row.into_static()),
+            LogRow::SpellSummon(row) => LogRow::SpellSummon(row.into_static()),
+            LogRow::SpellCreate(row) => LogRow::SpellCreate(row.into_static()),
+            LogRow::CombatLogVersion(row) => LogRow::CombatLogVersion(row.into_static()),
+            LogRow::CombatantInfo(row) => LogRow::CombatantInfo(row.into_static()),
+            LogRow::UnitDied(row) => LogRow::UnitDied(row.into_static()),
+            LogRow::UnitDestroyed(row) => LogRow::UnitDestroyed(row.into_static()),
+            LogRow::UnitDissipates(row) => LogRow::UnitDissipates(row.into_static()),
+            LogRow::PartyKill(row) => LogRow::PartyKill(row.into_static()),
+            LogRow::EncounterStart(row) => LogRow::EncounterStart(row.into_static()),
+            LogRow::EncounterEnd(row) => LogRow::EncounterEnd(row.into_static()),
+            LogRow::ZoneChange(row) => LogRow::ZoneChange(row.into_static()),
+            LogRow::SwingDamage(row) => LogRow::SwingDamage(row.into_static()),
+            LogRow::SwingMissed(row) => LogRow::SwingMissed(row.into_static()),
+            LogRow::NotSupported => LogRow::NotSupported,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+use std::vec;
+
+use rayon::prelude::*;
+
+use super::error::ParseError;
+use super::layout::LogLayout;
+use super::mapped::MappedRows;
+use super::rows::{Event, LineParser};
+use super::time::LogClock;
+
+// Large enough to make scheduling a chunk cheap next to parsing it, small
+// enough to keep every core busy until the end of the log.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// A newline-aligned part of the log, starting at byte `offset`.
+struct Chunk<'a> {
+    offset: usize,
+    data: &'a [u8],
+}
+
+struct ParsedChunk<'a> {
+    rows: Vec<Result<Event<'a>, ParseError>>,
+    lines: usize,
+    start_layout: LogLayout,
+    end_layout: LogLayout,
+}
+
+// Line numbers and timestamps of the rows are relative to the start of the chunk.
+fn parse_chunk<'a>(chunk: &Chunk<'a>, layout: LogLayout) -> ParsedChunk<'a> {
+    let mut rows = MappedRows::new(chunk.data, LineParser::starting_at(chunk.offset, layout));
+    let parsed: Vec<_> = rows.by_ref().collect();
+
+    ParsedChunk {
+        rows: parsed,
+        lines: rows.line_parser().lines(),
+        start_layout: layout,
+        end_layout: rows.line_parser().layout(),
+    }
+}
+
+// The layout declared by the header on the first line, so that the chunks of
+// the first batch do not all have to be parsed again for older logs.
+fn first_layout(data: &[u8]) -> LogLayout {
+    let mut lines = LineParser::new();
+    if let Some(first) = data.split(|c| *c == b'\n').next() {
+        let _ = lines.parse(first);
+    }
+
+    lines.layout()
+}
+
+/// Iterator over the rows of a `LogFile`, created by `LogFile::par_rows`.
+///
+/// The log is split into newline-aligned chunks, and a batch of chunks is
+/// parsed on the rayon thread pool whenever the rows parsed so far run out.
+/// Every chunk of a batch is parsed with the layout in effect at the start of
+/// the batch. The rare chunk that follows a COMBAT_LOG_VERSION header with a
+/// different layout is parsed again once its real layout is known.
+pub struct ParRows<'a> {
+    data: &'a [u8],
+    offset: usize,
+    chunk_size: usize,
+    layout: LogLayout,
+    clock: LogClock,
+    lines: usize,
+    // Parsed chunks that have not been yielded yet, next to the number of lines
+    // before each of them.
+    pending: VecDeque<(usize, vec::IntoIter<Result<Event<'a>, ParseError>>)>,
+}
+
+impl<'a> ParRows<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self::with_chunk_size(data, CHUNK_SIZE)
+    }
+
+    fn with_chunk_size(data: &'a [u8], chunk_size: usize) -> Self {
+        Self {
+            data,
+            offset: 0,
+            chunk_size,
+            layout: first_layout(data),
+            clock: LogClock::new(),
+            lines: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn next_chunk(&mut self) -> Option<Chunk<'a>> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let end = self
+            .data
+            .iter()
+            .skip(self.chunk_size)
+            .position(|c| *c == b'\n')
+            .map_or(self.data.len(), |newline| self.chunk_size + newline + 1);
+        let (data, rest) = self.data.split_at(end);
+        let chunk = Chunk {
+            offset: self.offset,
+            data,
+        };
+        self.data = rest;
+        self.offset += end;
+
+        Some(chunk)
+    }
+
+    fn parse_batch(&mut self) {
+        let chunks: Vec<Chunk> = (0..rayon::current_num_threads() * 2)
+            .map_while(|_| self.next_chunk())
+            .collect();
+
+        let layout = self.layout;
+        let parsed: Vec<ParsedChunk> = chunks
+            .par_iter()
+            .map(|chunk| parse_chunk(chunk, layout))
+            .collect();
+
+        for (chunk, mut parsed) in chunks.iter().zip(parsed) {
+            if parsed.start_layout != self.layout {
+                parsed = parse_chunk(chunk, self.layout);
+            }
+
+            self.pending
+                .push_back((self.lines, parsed.rows.into_iter()));
+            self.lines += parsed.lines;
+            self.layout = parsed.end_layout;
+        }
+    }
+}
+
+impl<'a> Iterator for ParRows<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((lines, rows)) = self.pending.front_mut() else {
+                if self.data.is_empty() {
+                    return None;
+                }
+                self.parse_batch();
+                continue;
+            };
+
+            // The rows are fixed up here rather than in `parse_batch` to avoid
+            // moving every row once more.
+            return Some(match rows.next() {
+                Some(Ok(mut event)) => {
+                    event.line += *lines;
+                    event.timestamp = self.clock.tick(&event.date_time);
+                    Ok(event)
+                }
+                Some(Err(mut err)) => {
+                    err.line += *lines;
+                    Err(err)
+                }
+                None => {
+                    self.pending.pop_front();
+                    continue;
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_serial_parse() {
+        let log = "4/29 20:15:03.123  COMBAT_LOG_VERSION,9,ADVANCED_LOG_ENABLED,0,BUILD_VERSION,3.4.3,PROJECT_ID,11\n\
+            4/29 20:15:04.000  SPELL_DAMAGE,Player-4395-0390A6B5,\"Yerrog-Whitemane\",0x512,0x0,Creature-0-4395-615-2719-28860-00004F3A5B,\"Sartharion\",0x10a48,0x0,47465,\"Rend\",0x1,2215,-1,1,0,0,0,1,nil,nil,1\n\
+            4/29 20:15:05.000  SPELL_DAMAGE,broken\n\
+            4/29 23:59:59.900  COMBAT_LOG_VERSION,20,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,10.1.7,PROJECT_ID,1\n\
+            4/30 00:00:00.100  SPELL_DAMAGE,Player-1379-0A9FF58F,\"Yerrog-Sanguino\",0x512,0x0,Creature-0-4252-2515-19964-196102-000550239A,\"Conjured Lasher\",0xa48,0x0,213709,\"Brambles\",0x8,Creature-0-4252-2515-19964-196102-000550239A,0000000000000000,1483954,1952835,0,0,5043,0,1,0,0,0,-5095.52,1142.47,2073,6.1556,70,488,488,-1,8,0,0,0,nil,nil,nil\n\
+            4/30 00:00:01.000  ZONE_CHANGE,2569,\"Aberrus, the Shadowed Crucible\",16";
+
+        let serial: Vec<_> = MappedRows::new(log.as_bytes(), LineParser::new()).collect();
+        // Every line ends up in a chunk of its own.
+        let parallel: Vec<_> = ParRows::with_chunk_size(log.as_bytes(), 1).collect();
+
+        assert_eq!(serial.len(), 6);
+        assert!(serial[1].is_ok());
+        assert!(serial[2].is_err());
+        assert!(serial[4].is_ok());
+        assert_eq!(parallel, serial);
+    }
+}
@@ -0,0 +1,198 @@
+use std::io::{self, BufRead};
+
+use super::cell::LogRow;
+use super::error::{event_name, ParseError, ParseErrorKind};
+use super::layout::LogLayout;
+use super::owned::IntoStatic;
+use super::parse_line;
+use super::time::{LogClock, LogEventDateTime};
+
+/// A row of the log. Rows from `Parser::rows` own their data, rows read from a
+/// `LogFile` borrow it from the mapped file.
+#[derive(Debug, PartialEq)]
+pub struct Event<'a> {
+    // 1-based line number in the log.
+    pub line: usize,
+    // Byte offset of the line in the log.
+    pub offset: usize,
+    pub date_time: LogEventDateTime,
+    // Milliseconds since the first row of the log.
+    pub timestamp: i64,
+    pub row: LogRow<'a>,
+}
+
+impl<'a> IntoStatic for Event<'a> {
+    type Static = Event<'static>;
+
+    fn into_static(self) -> Self::Static {
+        Event {
+            line: self.line,
+            offset: self.offset,
+            date_time: self.date_time,
+            timestamp: self.timestamp,
+            row: self.row.into_static(),
+        }
+    }
+}
+
+/// Parses the lines of a log one after the other. The layout is switched
+/// whenever a COMBAT_LOG_VERSION header is read, and the clock runs from the
+/// first row.
+pub(super) struct LineParser {
+    line: usize,
+    offset: usize,
+    layout: LogLayout,
+    clock: LogClock,
+}
+
+impl LineParser {
+    pub fn new() -> Self {
+        Self::starting_at(0, LogLayout::default())
+    }
+
+    /// A parser for a part of a log starting at byte `offset`, with `layout` in
+    /// effect. Line numbers and timestamps are counted from the start of the part.
+    pub fn starting_at(offset: usize, layout: LogLayout) -> Self {
+        Self {
+            line: 0,
+            offset,
+            layout,
+            clock: LogClock::new(),
+        }
+    }
+
+    pub fn lines(&self) -> usize {
+        self.line
+    }
+
+    pub fn layout(&self) -> LogLayout {
+        self.layout
+    }
+
+    /// Parses the next line of the log, including its line ending. Blank lines
+    /// give `None`.
+    pub fn parse<'a>(&mut self, bytes: &'a [u8]) -> Result<Option<Event<'a>>, ParseError> {
+        self.line += 1;
+        let line_offset = self.offset;
+        self.offset += bytes.len();
+
+        let line = std::str::from_utf8(bytes).map_err(|err| ParseError {
+            line: self.line,
+            offset: line_offset + err.valid_up_to(),
+            event: event_name(&String::from_utf8_lossy(bytes)),
+            kind: ParseErrorKind::InvalidUtf8,
+        })?;
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        let (date_time, row) = parse_line(self.line, line_offset, line, &self.layout)?;
+        // The header is written again whenever logging is restarted.
+        if let LogRow::CombatLogVersion(header) = &row {
+            self.layout = LogLayout::from(header);
+        }
+
+        Ok(Some(Event {
+            line: self.line,
+            offset: line_offset,
+            date_time,
+            timestamp: self.clock.tick(&date_time),
+            row,
+        }))
+    }
+
+    /// The error for a read of the next line that failed.
+    pub fn io_error(&self, err: io::Error) -> ParseError {
+        ParseError {
+            line: self.line + 1,
+            offset: self.offset,
+            event: "?".to_string(),
+            kind: ParseErrorKind::Io(err.to_string()),
+        }
+    }
+}
+
+/// Iterator over the rows of a log, created by `Parser::rows`.
+///
+/// Lines that cannot be parsed are yielded as errors and skipped. Reaching the
+/// end of the reader ends the iteration, but calling `next` again picks up lines
+/// that were appended in the meantime.
+pub struct Rows<R> {
+    reader: R,
+    buf: Vec<u8>,
+    lines: LineParser,
+    failed: bool,
+}
+
+impl<R: BufRead> Rows<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            lines: LineParser::new(),
+            failed: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Rows<R> {
+    type Item = Result<Event<'static>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => {
+                    // Reads are not retried, the reader is likely gone for good.
+                    self.failed = true;
+                    return Some(Err(self.lines.io_error(err)));
+                }
+            }
+
+            match self.lines.parse(&self.buf) {
+                Ok(Some(event)) => return Some(Ok(event.into_static())),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn streams_owned_rows() {
+        let log = "4/29 20:15:03.123  COMBAT_LOG_VERSION,9,ADVANCED_LOG_ENABLED,0,BUILD_VERSION,3.4.3,PROJECT_ID,11\n\
+            4/29 20:15:04.000  SPELL_DAMAGE,Player-4395-01C5EEA4,\"Yerrog-Pagle\",0x511,0x0,Creature-0-4395-615-2425-28860-00004DD9A8,\"Sartharion\",0x10a48,0x0,48638,\"Sinister Strike\",0x1,2215,0,1,0,0,0,1,nil,nil,1\r\n\
+            \n\
+            4/29 20:15:05.000  SPELL_DAMAGE,broken\n\
+            4/29 20:15:06.500  ZONE_CHANGE,2569,\"Aberrus, the Shadowed Crucible\",16\n";
+
+        let rows: Vec<Result<Event<'static>, ParseError>> = Parser::rows(log.as_bytes()).collect();
+
+        assert_eq!(rows.len(), 4);
+        let damage = rows[1].as_ref().unwrap();
+        assert_eq!(damage.line, 2);
+        assert_eq!(damage.offset, 97);
+        assert_eq!(damage.timestamp, 877);
+        match &damage.row {
+            LogRow::SpellDamage(damage) => {
+                assert_eq!(damage.sourceName.as_str(), Some("Yerrog-Pagle"));
+                assert_eq!(damage.advanced, None);
+            }
+            row => panic!("Expected spell damage, got {:?}", row),
+        }
+        let err = rows[2].as_ref().unwrap_err();
+        assert_eq!(err.line, 4);
+        assert_eq!(err.event, "SPELL_DAMAGE");
+        assert_eq!(rows[3].as_ref().unwrap().timestamp, 3377);
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use bitflags::bitflags;
+
+use super::cell::LogCell;
+use super::flags::parse_hex_bits;
+
+bitflags! {
+    /// SCHOOL_MASK_* bits. Spells that deal more than one kind of damage set
+    /// several bits, e.g. Frostfire is `FIRE | FROST`.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+    pub struct SpellSchool: u8 {
+        const PHYSICAL = 0x01;
+        const HOLY = 0x02;
+        const FIRE = 0x04;
+        const NATURE = 0x08;
+        const FROST = 0x10;
+        const SHADOW = 0x20;
+        const ARCANE = 0x40;
+    }
+}
+
+// Names the game gives to single and combined schools.
+const SCHOOL_NAMES: [(u8, &str); 32] = [
+    (0x01, "Physical"),
+    (0x02, "Holy"),
+    (0x04, "Fire"),
+    (0x08, "Nature"),
+    (0x10, "Frost"),
+    (0x20, "Shadow"),
+    (0x40, "Arcane"),
+    (0x03, "Holystrike"),
+    (0x05, "Flamestrike"),
+    (0x06, "Radiant"),
+    (0x09, "Stormstrike"),
+    (0x0a, "Holystorm"),
+    (0x0c, "Volcanic"),
+    (0x11, "Froststrike"),
+    (0x12, "Holyfrost"),
+    (0x14, "Frostfire"),
+    (0x18, "Froststorm"),
+    (0x1c, "Elemental"),
+    (0x21, "Shadowstrike"),
+    (0x22, "Twilight"),
+    (0x24, "Shadowflame"),
+    (0x28, "Plague"),
+    (0x30, "Shadowfrost"),
+    (0x3e, "Chromatic"),
+    (0x41, "Spellstrike"),
+    (0x42, "Divine"),
+    (0x44, "Spellfire"),
+    (0x48, "Astral"),
+    (0x50, "Spellfrost"),
+    (0x60, "Spellshadow"),
+    (0x6a, "Cosmic"),
+    (0x7f, "Chaos"),
+];
+
+// The default combat log colors of the single schools, as RGB.
+const SCHOOL_COLORS: [(SpellSchool, (u8, u8, u8)); 7] = [
+    (SpellSchool::PHYSICAL, (0xff, 0xff, 0x00)),
+    (SpellSchool::HOLY, (0xff, 0xe6, 0x80)),
+    (SpellSchool::FIRE, (0xff, 0x80, 0x00)),
+    (SpellSchool::NATURE, (0x4d, 0xff, 0x4d)),
+    (SpellSchool::FROST, (0x80, 0xff, 0xff)),
+    (SpellSchool::SHADOW, (0x80, 0x80, 0xff)),
+    (SpellSchool::ARCANE, (0xff, 0x80, 0xff)),
+];
+
+impl SpellSchool {
+    /// Whether more than one school is set, e.g. Shadowflame.
+    pub fn is_combined(&self) -> bool {
+        self.bits().count_ones() > 1
+    }
+
+    /// The name of the school, falling back to the single schools joined by `/`
+    /// for combinations the game has no name for.
+    pub fn name(&self) -> String {
+        if let Some((_, name)) = SCHOOL_NAMES.iter().find(|(bits, _)| *bits == self.bits()) {
+            return name.to_string();
+        }
+        if self.is_empty() {
+            return "None".to_string();
+        }
+
+        self.iter()
+            .map(|school| school.name())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// A CSS color for the school. Combined schools blend the colors of their parts.
+    pub fn color(&self) -> String {
+        let colors: Vec<(u8, u8, u8)> = SCHOOL_COLORS
+            .iter()
+            .filter(|(school, _)| self.contains(*school))
+            .map(|(_, color)| *color)
+            .collect();
+        if colors.is_empty() {
+            return "#ffffff".to_string();
+        }
+
+        let blend = |channel: fn(&(u8, u8, u8)) -> u8| {
+            colors.iter().map(|c| channel(c) as usize).sum::<usize>() / colors.len()
+        };
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            blend(|c| c.0),
+            blend(|c| c.1),
+            blend(|c| c.2)
+        )
+    }
+}
+
+impl fmt::Display for SpellSchool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// The spell prefix writes the school as hex (`0x24`), the damage suffix as a
+// decimal number (`36`).
+impl<'a> From<LogCell<'a>> for SpellSchool {
+    fn from(cell: LogCell) -> Self {
+        SpellSchool::from_bits_truncate(parse_hex_bits(cell) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_spell_schools() {
+        let shadowflame = SpellSchool::from(LogCell::Str("24".into()));
+        assert_eq!(shadowflame, SpellSchool::FIRE | SpellSchool::SHADOW);
+        assert!(shadowflame.is_combined());
+        assert_eq!(shadowflame.to_string(), "Shadowflame");
+
+        assert_eq!(SpellSchool::from(LogCell::Integer(20)).name(), "Frostfire");
+        assert_eq!(SpellSchool::from(LogCell::Str("7f".into())).name(), "Chaos");
+        assert_eq!(SpellSchool::from(LogCell::Integer(1)).name(), "Physical");
+        assert_eq!(
+            (SpellSchool::HOLY | SpellSchool::SHADOW | SpellSchool::ARCANE).name(),
+            "Holy/Shadow/Arcane"
+        );
+
+        assert_eq!(SpellSchool::PHYSICAL.color(), "#ffff00");
+        assert_eq!(shadowflame.color(), "#bf807f");
+    }
+}
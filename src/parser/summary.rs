@@ -0,0 +1,124 @@
+use super::cell::LogRow;
+use super::encounter::EncounterSegmenter;
+use super::layout::LogLayout;
+use super::parse_line;
+use super::time::{parse_date_time, LogClock};
+
+// The only rows a summary needs. Every other line is skipped without being parsed.
+const SUMMARY_EVENTS: [&[u8]; 3] = [b"ZONE_CHANGE,", b"ENCOUNTER_START,", b"ENCOUNTER_END,"];
+
+/// What a log contains at a glance, from `LogSummary::scan` or `LogFile::summary`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LogSummary {
+    // The zones that were entered, in the order they were first entered.
+    pub zones: Vec<String>,
+    pub encounters: usize,
+    pub kills: usize,
+    // Milliseconds from the first to the last row.
+    pub duration: i64,
+}
+
+impl LogSummary {
+    pub fn wipes(&self) -> usize {
+        self.encounters - self.kills
+    }
+
+    /// Scans a log for its zones and pulls. Much faster than parsing it, as only
+    /// the timestamps of the first and last rows and the zone and encounter rows
+    /// are parsed. Lines that cannot be parsed are ignored.
+    pub fn scan(data: &[u8]) -> Self {
+        let mut summary = Self::default();
+        let mut clock = LogClock::new();
+        let mut segmenter = EncounterSegmenter::new();
+        let layout = LogLayout::default();
+        let mut started = false;
+
+        for (index, line) in data.split(|c| *c == b'\n').enumerate() {
+            let Some(separator) = line.windows(2).position(|pair| pair == b"  ") else {
+                continue;
+            };
+            let wanted = SUMMARY_EVENTS
+                .iter()
+                .any(|event| line[separator + 2..].starts_with(event));
+            if started && !wanted {
+                continue;
+            }
+            let Ok(line) = std::str::from_utf8(line) else {
+                continue;
+            };
+            let line = line.trim_end_matches('\r');
+
+            if !wanted {
+                if let Ok((_, date_time)) = parse_date_time(line) {
+                    clock.tick(&date_time);
+                    started = true;
+                }
+                continue;
+            }
+            let Ok((date_time, row)) = parse_line(index + 1, 0, line, &layout) else {
+                continue;
+            };
+            let timestamp = clock.tick(&date_time);
+            started = true;
+
+            if let LogRow::ZoneChange(zone) = &row {
+                let name = zone.zoneName.as_str().unwrap_or_default();
+                if !summary.zones.iter().any(|zone| zone == name) {
+                    summary.zones.push(name.to_string());
+                }
+            }
+            segmenter.push(index, 0, timestamp, &row);
+        }
+
+        let last_row = data
+            .rsplit(|c| *c == b'\n')
+            .find_map(|line| parse_date_time(std::str::from_utf8(line).ok()?).ok());
+        if let Some((_, date_time)) = last_row {
+            summary.duration = clock.tick(&date_time);
+        }
+
+        let encounters = segmenter.finish();
+        summary.encounters = encounters.len();
+        summary.kills = encounters
+            .iter()
+            .filter(|encounter| encounter.success)
+            .count();
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_zones_and_pulls() {
+        let log = "4/29 20:00:00.000  COMBAT_LOG_VERSION,20,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,10.1.0,PROJECT_ID,1\n\
+            4/29 20:00:01.000  ZONE_CHANGE,2569,\"Aberrus, the Shadowed Crucible\",16\n\
+            4/29 20:15:03.123  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n\
+            4/29 20:16:38.135  ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,0,95012\n\
+            4/29 20:20:00.000  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n\
+            4/29 20:25:10.442  ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,1,310442\n\
+            4/29 20:30:00.000  ZONE_CHANGE,2444,\"Dragon Isles\",0\n\
+            4/29 20:40:00.000  ZONE_CHANGE,2569,\"Aberrus, the Shadowed Crucible\",16\n\
+            4/29 20:41:00.000  ENCOUNTER_END,broken\n\
+            4/29 20:59:59.000  EMOTE,Creature-0-4252-2569-19964-201320-000550239A,\"Rashok\",0000000000000000,nil,Enough!\n\
+            \n";
+
+        let summary = LogSummary::scan(log.as_bytes());
+        assert_eq!(
+            summary,
+            LogSummary {
+                zones: vec![
+                    "Aberrus, the Shadowed Crucible".to_string(),
+                    "Dragon Isles".to_string(),
+                ],
+                encounters: 2,
+                kills: 1,
+                duration: 3_599_000,
+            }
+        );
+        assert_eq!(summary.wipes(), 1);
+    }
+}
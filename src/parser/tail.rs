@@ -0,0 +1,270 @@
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use super::error::ParseError;
+use super::owned::IntoStatic;
+use super::rows::{Event, LineParser};
+
+/// Follows a log that the game is still writing to, created by `LogTail::open`.
+///
+/// Like `Rows`, iteration ends once every row written so far has been read, and
+/// calling `next` again picks up the rows written since. A last line the game
+/// has only written in part is held back until it is complete.
+///
+/// A log that shrinks, no longer starts with the same bytes or is replaced by
+/// another file was started over, e.g. after `/combatlog` was toggled, and is
+/// read again from the start. The iteration ends when that is noticed, so that
+/// the rows of the old and the new log never come up in the same pass, and
+/// `restarted` tells the caller to forget the rows read before.
+pub struct LogTail {
+    path: PathBuf,
+    reader: BufReader<File>,
+    id: Option<FileId>,
+    buf: Vec<u8>,
+    lines: LineParser,
+    // Bytes read from the file, including a line that is held back.
+    read: u64,
+    // The first bytes read from the file, the start of its COMBAT_LOG_VERSION header.
+    head: Vec<u8>,
+    // Whether the last read found no more bytes.
+    at_end: bool,
+    restarted: bool,
+    failed: bool,
+}
+
+// Enough of the header to tell two logs apart by the timestamp it starts with.
+const HEAD_SIZE: usize = 64;
+
+// Tells files apart, so that a log that is replaced while it is followed is noticed.
+type FileId = (u64, u64);
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(metadata: &Metadata) -> Option<FileId> {
+    let created = metadata.created().ok()?;
+    let created = created.duration_since(std::time::UNIX_EPOCH).ok()?;
+
+    Some((created.as_secs(), created.subsec_nanos().into()))
+}
+
+impl LogTail {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+
+        Ok(Self {
+            id: file_id(&file.metadata()?),
+            reader: BufReader::new(file),
+            path,
+            buf: Vec::new(),
+            lines: LineParser::new(),
+            read: 0,
+            head: Vec::new(),
+            at_end: false,
+            restarted: false,
+            failed: false,
+        })
+    }
+
+    /// Whether the log was started over since the last call. The rows read
+    /// before belong to the old log.
+    pub fn restarted(&mut self) -> bool {
+        std::mem::take(&mut self.restarted)
+    }
+
+    // Whether the file at the path is no longer the one being read, or was cut
+    // short. A log truncated in place may have grown past `read` again by the
+    // time it is polled, so its first bytes are compared too. While there is no
+    // file at the path, the old one is kept.
+    fn was_restarted(&self) -> io::Result<bool> {
+        let current = match fs::metadata(&self.path) {
+            Ok(current) => current,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        if file_id(&current) != self.id || current.len() < self.read {
+            return Ok(true);
+        }
+
+        let mut head = vec![0; self.head.len()];
+        match File::open(&self.path)?.read_exact(&mut head) {
+            Ok(()) => Ok(head != self.head),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(true),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Reads up to the end of the next line into `buf`, false if the line is not
+    // complete yet or the log was started over. What was written since the end
+    // was last reached may belong to a new log, so that is checked first.
+    fn read_line(&mut self) -> io::Result<bool> {
+        if self.at_end && self.was_restarted()? {
+            let file = File::open(&self.path)?;
+            self.id = file_id(&file.metadata()?);
+            self.reader = BufReader::new(file);
+            self.buf.clear();
+            self.lines = LineParser::new();
+            self.read = 0;
+            self.head.clear();
+            self.at_end = false;
+            self.restarted = true;
+            return Ok(false);
+        }
+
+        let read = self.reader.read_until(b'\n', &mut self.buf)?;
+        self.at_end = read == 0;
+        self.read += read as u64;
+        if self.head.len() < HEAD_SIZE {
+            let new = &self.buf[self.buf.len() - read..];
+            let missing = HEAD_SIZE - self.head.len();
+            self.head.extend_from_slice(&new[..read.min(missing)]);
+        }
+
+        Ok(self.buf.ends_with(b"\n"))
+    }
+}
+
+impl Iterator for LogTail {
+    type Item = Result<Event<'static>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            match self.read_line() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(self.lines.io_error(err)));
+                }
+            }
+
+            let row = self
+                .lines
+                .parse(&self.buf)
+                .map(|event| event.map(IntoStatic::into_static));
+            self.buf.clear();
+            match row {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    use super::*;
+    use crate::parser::LogRow;
+
+    #[test]
+    fn follows_growing_log() {
+        let path =
+            std::env::temp_dir().join(format!("wow-log-analyzer-tail-{}.txt", std::process::id()));
+        fs::write(
+            &path,
+            "4/29 20:15:03.123  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n\
+            4/29 20:16:38.135  ENCOUNTER_END,2688,\"Rashok",
+        )
+        .unwrap();
+        let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+        let mut tail = LogTail::open(&path).unwrap();
+
+        let rows: Vec<_> = tail.by_ref().collect();
+        assert_eq!(rows.len(), 1);
+        assert!(matches!(
+            rows[0],
+            Ok(Event {
+                row: LogRow::EncounterStart(_),
+                ..
+            })
+        ));
+
+        // The rest of the partially written line.
+        writeln!(log, ", the Elder\",16,20,0,95012").unwrap();
+        let rows: Vec<_> = tail.by_ref().collect();
+        assert_eq!(rows.len(), 1);
+        let end = rows[0].as_ref().unwrap();
+        assert_eq!(end.line, 2);
+        assert_eq!(end.timestamp, 95012);
+        assert!(matches!(end.row, LogRow::EncounterEnd(_)));
+
+        assert!(!tail.restarted());
+
+        // Logging was restarted.
+        fs::write(
+            &path,
+            "4/29 21:00:00.000  ENCOUNTER_END,2688,\"Rashok, the Elder\",16,20,1,310442\n",
+        )
+        .unwrap();
+        assert_eq!(tail.by_ref().count(), 0);
+        assert!(tail.restarted());
+        let rows: Vec<_> = tail.by_ref().collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].as_ref().unwrap().line, 1);
+        assert!(!tail.restarted());
+
+        drop(log);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn follows_replaced_log() {
+        let path = std::env::temp_dir().join(format!(
+            "wow-log-analyzer-tail-replaced-{}.txt",
+            std::process::id()
+        ));
+        let start = "4/29 20:15:03.123  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n";
+        fs::write(&path, start).unwrap();
+        let mut tail = LogTail::open(&path).unwrap();
+        assert_eq!(tail.by_ref().count(), 1);
+
+        // A new log is written in place of the old one, larger than what was read.
+        let replacement = path.with_extension("new");
+        fs::write(&replacement, start.repeat(3)).unwrap();
+        fs::rename(&replacement, &path).unwrap();
+        assert_eq!(tail.by_ref().count(), 0);
+        assert!(tail.restarted());
+        let rows: Vec<_> = tail.by_ref().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2].as_ref().unwrap().line, 3);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn follows_log_truncated_and_grown() {
+        let path = std::env::temp_dir().join(format!(
+            "wow-log-analyzer-tail-truncated-{}.txt",
+            std::process::id()
+        ));
+        let start = "4/29 20:15:03.123  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n";
+        fs::write(&path, start).unwrap();
+        let mut tail = LogTail::open(&path).unwrap();
+        assert_eq!(tail.by_ref().count(), 1);
+
+        // Logging was restarted in the same file, which grew past what was read
+        // before the next poll. Resuming at the old position would land mid-line.
+        let restart = "10/29 21:00:00.000  ENCOUNTER_START,2688,\"Rashok, the Elder\",16,20,2569\n";
+        fs::write(&path, restart.repeat(2)).unwrap();
+        assert_eq!(tail.by_ref().count(), 0);
+        assert!(tail.restarted());
+        let rows: Vec<_> = tail.by_ref().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].as_ref().unwrap().line, 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::{digit1, one_of},
+    combinator::{map, map_res, opt, recognize},
+    sequence::{pair, preceded, separated_pair, tuple},
+};
+
+use super::error::IResult;
+
+const MS_PER_MINUTE: i64 = 60 * 1000;
+const MS_PER_DAY: i64 = 24 * 60 * MS_PER_MINUTE;
+
+// Logs without a year are placed in a leap year, so that Feb 29 is a valid date.
+const DEFAULT_YEAR: i64 = 2000;
+
+// How far a row may lag behind the one before it and still count as written out
+// of order rather than as the clock being turned back.
+const OUT_OF_ORDER_MS: i64 = MS_PER_MINUTE;
+
+/// The timestamp in front of every row, e.g. `4/29 20:15:03.123` or, in newer
+/// retail logs, `10/16/2026 20:15:03.123-4`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LogEventDateTime {
+    // The year an event occurred, only written by newer retail logs
+    pub year: Option<i64>,
+    // The month an event occurred
+    pub month: u32,
+    // The day of the month an event occurred
+    pub day: u32,
+    // The hour an event occured
+    pub hour: u32,
+    // The minute an event occured
+    pub minute: u32,
+    // The second event occured
+    pub second: u32,
+    // The millisecond event occured
+    pub ms: u32,
+    // Offset of the local time from UTC in minutes, only written by newer retail logs
+    pub utc_offset: Option<i64>,
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+impl LogEventDateTime {
+    /// Milliseconds since 1970-01-01 on the local clock of the player who wrote
+    /// the log. `year` is used when the timestamp does not carry one.
+    pub fn wall_clock_ms(&self, year: i64) -> i64 {
+        let days = days_from_civil(self.year.unwrap_or(year), self.month, self.day);
+        let time = ((self.hour as i64 * 60 + self.minute as i64) * 60 + self.second as i64) * 1000
+            + self.ms as i64;
+
+        days * MS_PER_DAY + time
+    }
+
+    /// Milliseconds since the Unix epoch, for logs that write both year and UTC offset.
+    pub fn unix_ms(&self) -> Option<i64> {
+        match (self.year, self.utc_offset) {
+            (Some(year), Some(offset)) => Some(self.wall_clock_ms(year) - offset * MS_PER_MINUTE),
+            _ => None,
+        }
+    }
+}
+
+/// Turns row timestamps into milliseconds since the first row of the log.
+///
+/// Dates are part of the calculation, so fights that run past midnight keep
+/// counting up. Logs without a year are assumed to roll over into the next one
+/// when December is followed by January. The result never goes backwards: rows
+/// written slightly out of order keep the time of the row before them, and when
+/// the clock is turned back for daylight saving time in logs that do not write
+/// their UTC offset, time goes on from where it was before the jump.
+#[derive(Debug)]
+pub struct LogClock {
+    // The time of the last row, in the milliseconds of `wall_clock_ms` or `unix_ms`.
+    last: Option<i64>,
+    year: i64,
+    last_month: u32,
+    elapsed: i64,
+}
+
+impl Default for LogClock {
+    fn default() -> Self {
+        Self {
+            last: None,
+            year: DEFAULT_YEAR,
+            last_month: 1,
+            elapsed: 0,
+        }
+    }
+}
+
+impl LogClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tick(&mut self, time: &LogEventDateTime) -> i64 {
+        if time.year.is_none() && self.last_month == 12 && time.month == 1 {
+            self.year += 1;
+        }
+        self.last_month = time.month;
+
+        let absolute = time
+            .unix_ms()
+            .unwrap_or_else(|| time.wall_clock_ms(self.year));
+        let last = *self.last.get_or_insert(absolute);
+        let delta = absolute - last;
+        if delta >= 0 {
+            self.elapsed += delta;
+            self.last = Some(absolute);
+        } else if delta < -OUT_OF_ORDER_MS {
+            // The clock was turned back, time goes on from here.
+            self.last = Some(absolute);
+        }
+
+        self.elapsed
+    }
+}
+
+fn number(input: &str) -> IResult<&str, u32> {
+    map_res(digit1, str::parse)(input)
+}
+
+// `-4` or `+5.5`, in hours.
+fn parse_utc_offset(input: &str) -> IResult<&str, i64> {
+    let parser = recognize(tuple((one_of("+-"), digit1, opt(pair(tag("."), digit1)))));
+
+    map_res(parser, |v: &str| {
+        v.parse::<f64>().map(|hours| (hours * 60.0).round() as i64)
+    })(input)
+}
+
+// Logs have written the fraction of a second with three or four digits.
+fn parse_ms(input: &str) -> IResult<&str, u32> {
+    map(digit1, |v: &str| {
+        v.chars()
+            .chain(std::iter::repeat('0'))
+            .take(3)
+            .collect::<String>()
+            .parse()
+            .unwrap_or_default()
+    })(input)
+}
+
+pub fn parse_date_time(input: &str) -> IResult<&str, LogEventDateTime> {
+    let date = tuple((
+        number,
+        tag("/"),
+        number,
+        opt(preceded(tag("/"), map_res(digit1, str::parse::<i64>))),
+    ));
+    let time = tuple((
+        number,
+        tag(":"),
+        number,
+        tag(":"),
+        number,
+        tag("."),
+        parse_ms,
+        opt(parse_utc_offset),
+    ));
+    let parser = separated_pair(date, tag(" "), time);
+
+    map(parser, |(date, time)| LogEventDateTime {
+        year: date.3,
+        month: date.0,
+        day: date.2,
+        hour: time.0,
+        minute: time.2,
+        second: time.4,
+        ms: time.6,
+        utc_offset: time.7,
+    })(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(clock: &mut LogClock, input: &str) -> i64 {
+        let (_, time) = parse_date_time(input).unwrap();
+        clock.tick(&time)
+    }
+
+    #[test]
+    fn parse_timestamps() {
+        let (remainder, classic) = parse_date_time("4/29 20:15:03.123  SPELL_DAMAGE").unwrap();
+        assert_eq!(remainder, "  SPELL_DAMAGE");
+        assert_eq!(classic.year, None);
+        assert_eq!(classic.month, 4);
+        assert_eq!(classic.ms, 123);
+        assert_eq!(classic.utc_offset, None);
+
+        let (_, retail) = parse_date_time("10/16/2026 20:15:03.1234-4").unwrap();
+        assert_eq!(retail.year, Some(2026));
+        assert_eq!(retail.ms, 123);
+        assert_eq!(retail.utc_offset, Some(-240));
+        // 2026-10-17 00:15:03.123 UTC
+        assert_eq!(retail.unix_ms(), Some(1_792_196_103_123));
+    }
+
+    #[test]
+    fn clock_survives_midnight_and_new_year() {
+        let mut clock = LogClock::new();
+        assert_eq!(tick(&mut clock, "12/31 23:59:59.500"), 0);
+        assert_eq!(tick(&mut clock, "1/1 00:00:00.250"), 750);
+        // Rows are occasionally written slightly out of order.
+        assert_eq!(tick(&mut clock, "1/1 00:00:00.100"), 750);
+        assert_eq!(tick(&mut clock, "1/1 00:01:00.250"), 60_750);
+
+        let mut clock = LogClock::new();
+        assert_eq!(tick(&mut clock, "12/31/2025 22:00:00.000-4"), 0);
+        assert_eq!(tick(&mut clock, "1/1/2026 00:30:00.000-4"), 9_000_000);
+    }
+
+    #[test]
+    fn clock_keeps_running_when_turned_back() {
+        let mut clock = LogClock::new();
+        assert_eq!(tick(&mut clock, "10/25 02:59:50.000"), 0);
+        // Daylight saving time ended, the hour from 2:00 is logged again.
+        assert_eq!(tick(&mut clock, "10/25 02:00:05.000"), 0);
+        assert_eq!(tick(&mut clock, "10/25 02:00:15.000"), 10_000);
+        assert_eq!(tick(&mut clock, "10/25 02:30:15.000"), 1_810_000);
+    }
+}
@@ -0,0 +1,260 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// The settings that are kept between runs, in `settings.toml` in the user's
+/// config directory.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // The directory with the combat logs. Detected when it is not set.
+    pub log_dir: Option<PathBuf>,
+    // The client whose log directory is detected.
+    pub flavor: Flavor,
+    // How far back the recap of a death goes, in seconds.
+    pub death_recap_seconds: u32,
+    // Whether pets are counted together with their owners.
+    pub merge_pets: bool,
+    // The names of the players that count as our raid. When empty, everyone in
+    // the group of the player who logged does.
+    pub raid_members: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            log_dir: None,
+            flavor: Flavor::Retail,
+            death_recap_seconds: 10,
+            merge_pets: true,
+            raid_members: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Where the settings are kept, e.g. `%APPDATA%\wow-log-analyzer\settings.toml` on Windows.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("wow-log-analyzer").join("settings.toml"))
+    }
+
+    /// The saved settings, or the defaults when none were saved.
+    pub fn load() -> anyhow::Result<Self> {
+        match Self::path().filter(|path| path.exists()) {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> anyhow::Result<Self> {
+        let settings = fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        toml::from_str(&settings).with_context(|| format!("Could not parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path().context("Could not find the config directory")?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Could not create {}", dir.display()))?;
+        }
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Could not write {}", path.display()))
+    }
+
+    /// The configured log directory, or else the one detected for the flavor, or
+    /// else the first one that was detected.
+    pub fn log_dir(&self) -> Option<PathBuf> {
+        self.log_dir
+            .clone()
+            .or_else(|| self.detected_log_dir(&detect_log_dirs()))
+    }
+
+    fn detected_log_dir(&self, dirs: &[LogDir]) -> Option<PathBuf> {
+        dirs.iter()
+            .find(|dir| dir.flavor == self.flavor)
+            .or(dirs.first())
+            .map(|dir| dir.path.clone())
+    }
+
+    /// The death recap window in milliseconds, as used by `DeathRecaps`. Recaps
+    /// go back at least a second.
+    pub fn death_recap_window(&self) -> i64 {
+        i64::from(self.death_recap_seconds.max(1)) * 1000
+    }
+}
+
+/// The game client a log directory belongs to.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Flavor {
+    Retail,
+    Classic,
+    ClassicEra,
+}
+
+impl Flavor {
+    pub const ALL: [Flavor; 3] = [Flavor::Retail, Flavor::Classic, Flavor::ClassicEra];
+
+    // The folder of the client in the install directory.
+    pub fn folder(&self) -> &'static str {
+        match self {
+            Flavor::Retail => "_retail_",
+            Flavor::Classic => "_classic_",
+            Flavor::ClassicEra => "_classic_era_",
+        }
+    }
+}
+
+impl fmt::Display for Flavor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Flavor::Retail => "Retail",
+            Flavor::Classic => "Classic",
+            Flavor::ClassicEra => "Classic Era",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LogDir {
+    pub flavor: Flavor,
+    pub path: PathBuf,
+}
+
+const INSTALL_DIR: &str = "World of Warcraft";
+
+// The directories World of Warcraft is usually installed in, inside a Windows
+// drive or a Wine prefix.
+fn install_dirs_in(drive: &Path) -> Vec<PathBuf> {
+    ["Program Files (x86)", "Program Files", "Games", ""]
+        .iter()
+        .map(|dir| drive.join(dir).join(INSTALL_DIR))
+        .collect()
+}
+
+// Every place World of Warcraft might be installed in on this machine.
+fn install_dirs() -> Vec<PathBuf> {
+    let mut drives: Vec<PathBuf> = Vec::new();
+    if cfg!(windows) {
+        drives.extend(["C:\\", "D:\\", "E:\\"].map(PathBuf::from));
+    }
+    if cfg!(target_os = "macos") {
+        drives.push(PathBuf::from("/Applications"));
+    }
+
+    // Wine prefixes: the one in `WINEPREFIX`, the default one, and the ones Lutris
+    // creates in `~/Games`.
+    let mut prefixes: Vec<PathBuf> = std::env::var_os("WINEPREFIX")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    if let Some(home) = dirs::home_dir() {
+        prefixes.push(home.join(".wine"));
+        if let Ok(games) = fs::read_dir(home.join("Games")) {
+            prefixes.extend(games.flatten().map(|game| game.path()));
+        }
+    }
+    drives.extend(prefixes.iter().map(|prefix| prefix.join("drive_c")));
+
+    drives
+        .iter()
+        .flat_map(|drive| install_dirs_in(drive))
+        .collect()
+}
+
+/// The log directories of every client found in `install_dirs`.
+pub fn detect_log_dirs_in(install_dirs: &[PathBuf]) -> Vec<LogDir> {
+    install_dirs
+        .iter()
+        .flat_map(|install_dir| {
+            Flavor::ALL.map(|flavor| LogDir {
+                flavor,
+                path: install_dir.join(flavor.folder()).join("Logs"),
+            })
+        })
+        .filter(|dir| dir.path.is_dir())
+        .collect()
+}
+
+/// The log directories of every client installed on this machine, retail first.
+pub fn detect_log_dirs() -> Vec<LogDir> {
+    let mut dirs = detect_log_dirs_in(&install_dirs());
+    dirs.sort_by_key(|dir| dir.flavor);
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_and_saves_log_dirs() {
+        let root =
+            std::env::temp_dir().join(format!("wow-log-analyzer-settings-{}", std::process::id()));
+        let prefix = root.join("Games").join("battlenet").join("drive_c");
+        let install_dir = prefix.join("Program Files (x86)").join(INSTALL_DIR);
+        fs::create_dir_all(install_dir.join("_retail_").join("Logs")).unwrap();
+        fs::create_dir_all(install_dir.join("_classic_era_").join("Logs")).unwrap();
+        // A client that was never started has no log directory yet.
+        fs::create_dir_all(install_dir.join("_classic_")).unwrap();
+
+        let dirs = detect_log_dirs_in(&install_dirs_in(&prefix));
+        assert_eq!(
+            dirs,
+            vec![
+                LogDir {
+                    flavor: Flavor::Retail,
+                    path: install_dir.join("_retail_").join("Logs"),
+                },
+                LogDir {
+                    flavor: Flavor::ClassicEra,
+                    path: install_dir.join("_classic_era_").join("Logs"),
+                },
+            ]
+        );
+
+        let path = root.join("settings.toml");
+        let mut settings = Settings {
+            log_dir: Some(dirs[1].path.clone()),
+            flavor: Flavor::ClassicEra,
+            death_recap_seconds: 15,
+            merge_pets: false,
+            raid_members: vec!["Yerrog-Sanguino".to_string()],
+        };
+        settings.save_to(&path).unwrap();
+        assert_eq!(Settings::load_from(&path).unwrap(), settings);
+        assert_eq!(settings.log_dir(), Some(dirs[1].path.clone()));
+        assert_eq!(settings.death_recap_window(), 15_000);
+        settings.death_recap_seconds = 0;
+        assert_eq!(settings.death_recap_window(), 1_000);
+
+        // Without a log directory, the one of the flavor is used.
+        settings.log_dir = None;
+        assert_eq!(settings.detected_log_dir(&dirs), Some(dirs[1].path.clone()));
+        settings.flavor = Flavor::Classic;
+        assert_eq!(settings.detected_log_dir(&dirs), Some(dirs[0].path.clone()));
+
+        // Settings saved by an older version are still read.
+        fs::write(&path, "log_dir = \"Logs\"\n").unwrap();
+        assert_eq!(
+            Settings::load_from(&path).unwrap(),
+            Settings {
+                log_dir: Some(PathBuf::from("Logs")),
+                ..Settings::default()
+            }
+        );
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(Settings::load_from(&path).unwrap(), Settings::default());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}